@@ -0,0 +1,502 @@
+// cli.rs
+//
+// A small command-line parser, so the animation can be tuned without recompiling.
+
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use donut::{
+    parse, ColorMode, Gradient, Implicit, Light, Mode, Object, Parametric, Ramp, Shape, Solid,
+    DEFAULT_DISTANCE, DEFAULT_SPEED_A, DEFAULT_SPEED_B, MAX_SPEED,
+};
+
+use crate::scene_file;
+
+pub const USAGE: &str = "\
+Usage: donut [OPTIONS]
+       donut compare [OPTIONS]
+
+Commands:
+  compare                  Render a whole rotation with both the fixed-point renderer and
+                           the --renderer reference (f64 by default), and report how far
+                           apart they are. Takes --size, --frames, --ramp and the speeds.
+
+Options:
+      --still              Print a single frame as plain text and exit
+      --angle-a <RAD>      Angle A of the still frame, in radians
+      --angle-b <RAD>      Angle B of the still frame, in radians
+  -o, --output <FILE>      Write the still frame to FILE instead of stdout
+      --size <COLSxROWS>   Size of the output in characters, instead of fitting the
+                           terminal (or 80x22 for a still frame)
+      --shape <NAME>       What to draw: torus, sphere, cube, cylinder, cone, mobius or
+                           klein [default: torus]
+      --model <FILE>       A mesh (.obj or .stl) or point cloud (.ply or .xyz) to draw
+                           instead of a shape, moved and scaled to fit
+      --smooth             Shade the model smoothly across its triangles instead of
+                           using its own normals or flat shading
+      --surface <FORMULAS> A surface to draw instead of a shape, given by formulas for x, y
+                           and z in terms of u and v, and optionally their ranges, like
+                           `x = (2 + cos v) cos u; y = (2 + cos v) sin u; z = sin v;
+                           u = 0..2pi; v = 0..2pi`
+      --implicit <EQUATION>
+                           A surface to raymarch instead of a shape, given by an equation
+                           in x, y and z, like `x^2 + y^2 + z^2 = 4`, drawn out to 3 from
+                           the origin or as far as `; bounds = <N>` says
+      --solid <SOLID>      A solid to raymarch instead of a shape, built up from shapes
+                           like `subtract(torus, move(0, -2, 1, sphere(1)))`; see the
+                           README for everything it can do
+      --object <OBJECT>    Add an object to a scene drawn instead of a shape, like `shape =
+                           torus; position = 0, 0, 1; scale = 0.5; spin = 0, 0, 0.05`.
+                           Give it once for each object; see the README for the rest
+      --scene <FILE>       Set up the whole scene from a file: the objects in it, their
+                           materials, the camera, lights, style and animation. Options
+                           after it override what it sets; see the README for the format
+      --raymarch           Draw the shape by casting a ray through every cell, so there
+                           are never gaps in it (not for the mobius strip or klein bottle)
+      --renderer <NAME>    Which math to render with: fixed (the original fixed-point
+                           integers, which can only draw the torus), f32 or f64
+                           [default: fixed for the torus, f64 for anything else]
+      --fps <N>            Frames per second, as an alternative to --delay
+      --delay <MS>         Pause between frames in milliseconds [default: 35]
+      --frames <N>         Stop after N frames
+      --duration <SECS>    Stop after SECS seconds
+      --speed-a <RAD>      Rotation of angle A per frame in radians, up to 0.25 either way
+                           [default: 0.0390625]
+      --speed-b <RAD>      Rotation of angle B per frame in radians, up to 0.25 either way
+                           [default: 0.01953125]
+      --spin <X,Y,Z>       Turn the picture about any axis instead of by angles A and B,
+                           at an angular velocity: about the axis X,Y,Z points along (x
+                           right, y down, z into the screen), at as many radians per frame
+                           as it's long. Give --speed-a or --speed-b with it to keep
+                           tumbling as well
+      --mode <MODE>        How to draw pixels: glyphs, braille or half-block
+                           [default: glyphs]
+      --ramp <RAMP>        Characters to show luminance with, darkest first: either
+                           classic, blocks, long, digits, or your own [default: classic]
+      --color <MODE>       Color the output: truecolor, 256, 16 or none [default: none]
+      --gradient <COLORS>  Comma-separated hex colors to map luminance onto,
+                           darkest first [default: #3b1d0b,#8a4b1c,#d4934a,#f5c98e,#ffb3d1]
+      --depth-shading      Darken the parts of the donut that are farther away
+      --alternate-screen   Draw on the alternate screen, keeping the scrollback clean
+  -h, --help               Print this help and exit
+";
+
+/// Which renderer to draw the donut with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RendererKind {
+    Fixed,
+    F32,
+    F64,
+}
+
+impl FromStr for RendererKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(RendererKind::Fixed),
+            "f32" => Ok(RendererKind::F32),
+            "f64" => Ok(RendererKind::F64),
+            _ => Err(format!(
+                "unknown renderer `{s}`, expected fixed, f32 or f64"
+            )),
+        }
+    }
+}
+
+/// Everything that can be set from the command line.
+#[derive(Debug)]
+pub struct Options {
+    pub compare: bool,
+    pub still: bool,
+    pub angle_a: Option<f64>,
+    pub angle_b: Option<f64>,
+    pub output: Option<PathBuf>,
+    pub size: Option<(usize, usize)>,
+    pub shape: Option<Shape>,
+    pub model: Option<PathBuf>,
+    pub smooth: bool,
+    pub surface: Option<Parametric>,
+    pub solid: Option<Solid>,
+    pub implicit: Option<Implicit>,
+    pub objects: Vec<Object>,
+    pub scene: Option<PathBuf>,
+    pub distance: f64,
+    pub zoom: f64,
+    pub lights: Vec<Light>,
+    pub raymarch: bool,
+    pub renderer: Option<RendererKind>,
+    pub delay: Duration,
+    pub frames: Option<u64>,
+    pub duration: Option<Duration>,
+    pub speed_a: f64,
+    pub speed_b: f64,
+    pub spin: [f64; 3],
+    pub mode: Mode,
+    pub ramp: Ramp,
+    pub color: ColorMode,
+    pub gradient: Gradient,
+    pub depth_shading: bool,
+    pub alternate_screen: bool,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            compare: false,
+            still: false,
+            angle_a: None,
+            angle_b: None,
+            output: None,
+            size: None,
+            shape: None,
+            model: None,
+            smooth: false,
+            surface: None,
+            solid: None,
+            implicit: None,
+            objects: Vec::new(),
+            scene: None,
+            distance: DEFAULT_DISTANCE,
+            zoom: 1.0,
+            lights: vec![Light::default()],
+            raymarch: false,
+            renderer: None,
+            delay: Duration::from_millis(35),
+            frames: None,
+            duration: None,
+            speed_a: DEFAULT_SPEED_A,
+            speed_b: DEFAULT_SPEED_B,
+            spin: [0.0; 3],
+            mode: Mode::Glyphs,
+            ramp: Ramp::default(),
+            color: ColorMode::None,
+            gradient: Gradient::default(),
+            depth_shading: false,
+            alternate_screen: false,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the given arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter().peekable();
+        let mut spin_given = false;
+        let mut speeds_given = [false; 2];
+
+        if args.next_if(|arg| arg == "compare").is_some() {
+            options.compare = true;
+        }
+
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{flag}`"))
+            };
+
+            match flag.as_str() {
+                "--still" => options.still = true,
+                "--angle-a" => options.angle_a = Some(parse_angle(&flag, &value()?)?),
+                "--angle-b" => options.angle_b = Some(parse_angle(&flag, &value()?)?),
+                "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+                "--size" => options.size = Some(parse_size(&flag, &value()?)?),
+                "--shape" => options.shape = Some(value()?.parse()?),
+                "--model" => options.model = Some(PathBuf::from(value()?)),
+                "--smooth" => options.smooth = true,
+                "--surface" => options.surface = Some(value()?.parse()?),
+                "--solid" => options.solid = Some(value()?.parse()?),
+                "--implicit" => options.implicit = Some(value()?.parse()?),
+                "--object" => options.objects.push(value()?.parse()?),
+                "--scene" => {
+                    let path = PathBuf::from(value()?);
+                    scene_file::load(&path, &mut options)
+                        .map_err(|error| format!("couldn't load {}: {error}", path.display()))?;
+                    options.scene = Some(path);
+                }
+                "--raymarch" => options.raymarch = true,
+                "--renderer" => options.renderer = Some(value()?.parse()?),
+                "--fps" => {
+                    let text = value()?;
+                    let fps: f64 = parse_number(&flag, &text)?;
+                    if !fps.is_finite() || fps <= 0.0 {
+                        return Err(format!(
+                            "`{flag}` must be a finite number greater than zero"
+                        ));
+                    }
+                    options.delay = Duration::try_from_secs_f64(1.0 / fps)
+                        .map_err(|_| format!("invalid value for `{flag}`: `{text}` is too slow"))?;
+                }
+                "--delay" => {
+                    options.delay = Duration::from_millis(parse_number(&flag, &value()?)?);
+                }
+                "--frames" => options.frames = Some(parse_number(&flag, &value()?)?),
+                "--duration" => {
+                    let seconds: f64 = parse_number(&flag, &value()?)?;
+                    options.duration = Some(
+                        Duration::try_from_secs_f64(seconds)
+                            .map_err(|_| format!("invalid duration for `{flag}`: {seconds}"))?,
+                    );
+                }
+                "--speed-a" => {
+                    options.speed_a = parse_speed(&flag, &value()?)?;
+                    speeds_given[0] = true;
+                }
+                "--speed-b" => {
+                    options.speed_b = parse_speed(&flag, &value()?)?;
+                    speeds_given[1] = true;
+                }
+                "--spin" => {
+                    options.spin = parse_vector(&flag, &value()?)?;
+                    spin_given = true;
+                }
+                "--mode" => options.mode = value()?.parse()?,
+                "--ramp" => options.ramp = value()?.parse()?,
+                "--color" => options.color = value()?.parse()?,
+                "--gradient" => options.gradient = value()?.parse()?,
+                "--depth-shading" => options.depth_shading = true,
+                "--alternate-screen" => options.alternate_screen = true,
+                "-h" | "--help" => options.help = true,
+                _ => return Err(format!("unrecognized argument `{flag}`")),
+            }
+        }
+
+        // `--spin` takes over from the tumble by angles A and B wherever it comes, but a
+        // speed that's given as well keeps that angle turning
+        if spin_given {
+            if !speeds_given[0] {
+                options.speed_a = 0.0;
+            }
+            if !speeds_given[1] {
+                options.speed_b = 0.0;
+            }
+        }
+
+        let still_only = [
+            ("--angle-a", options.angle_a.is_some()),
+            ("--angle-b", options.angle_b.is_some()),
+            ("--output", options.output.is_some()),
+        ];
+        if let Some((flag, _)) = still_only
+            .iter()
+            .find(|(_, given)| *given && !options.still)
+        {
+            return Err(format!("`{flag}` can only be used with `--still`"));
+        }
+
+        let sources: Vec<_> = [
+            ("--shape", options.shape.is_some()),
+            ("--model", options.model.is_some()),
+            ("--surface", options.surface.is_some()),
+            ("--solid", options.solid.is_some()),
+            ("--implicit", options.implicit.is_some()),
+            // A scene file's objects go in with any given by `--object`
+            match options.scene {
+                Some(_) => ("--scene", !options.objects.is_empty()),
+                None => ("--object", !options.objects.is_empty()),
+            },
+        ]
+        .into_iter()
+        .filter_map(|(flag, given)| given.then_some(flag))
+        .collect();
+        if let [first, second, ..] = sources[..] {
+            return Err(format!("`{first}` and `{second}` can't be used together"));
+        }
+        let other_source =
+            options.model.is_some() || options.surface.is_some() || !options.objects.is_empty();
+        if options.raymarch && other_source {
+            return Err("`--raymarch` can only draw the built-in shapes".to_string());
+        }
+        if options.raymarch && options.shape.unwrap_or_default().solid().is_none() {
+            return Err(
+                "`--raymarch` can't draw the mobius strip or klein bottle, which aren't solid"
+                    .to_string(),
+            );
+        }
+        let fixed_point = options.compare || options.renderer == Some(RendererKind::Fixed);
+        let raymarched = options.raymarch || options.solid.is_some() || options.implicit.is_some();
+        if fixed_point && raymarched {
+            return Err("the fixed-point renderer can't raymarch".to_string());
+        }
+        if fixed_point && !options.draws_torus() {
+            return Err("the fixed-point renderer can only draw the torus".to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// Whether it's the plain old donut that's being drawn, the way the fixed-point renderer
+    /// draws it.
+    pub fn draws_torus(&self) -> bool {
+        !self.raymarch
+            && self.model.is_none()
+            && self.surface.is_none()
+            && self.solid.is_none()
+            && self.implicit.is_none()
+            && self.objects.is_empty()
+            && self.scene.is_none()
+            && self.spin == [0.0; 3]
+            && self.shape.is_none_or(|shape| shape == Shape::Torus)
+    }
+}
+
+// Parses a size given as `COLUMNSxROWS`, like `80x22`
+fn parse_size(flag: &str, value: &str) -> Result<(usize, usize), String> {
+    let (columns, rows) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("invalid value for `{flag}`: `{value}`, expected COLSxROWS"))?;
+    Ok((parse_number(flag, columns)?, parse_number(flag, rows)?))
+}
+
+// Parses an angle in radians, which has to be somewhere in particular
+fn parse_angle(flag: &str, value: &str) -> Result<f64, String> {
+    let angle: f64 = parse_number(flag, value)?;
+    if !angle.is_finite() {
+        return Err(format!("`{flag}` must be a finite number of radians"));
+    }
+    Ok(angle)
+}
+
+// Parses a rotation speed, which the fixed-point renderer can only turn so fast
+fn parse_speed(flag: &str, value: &str) -> Result<f64, String> {
+    let speed: f64 = parse_number(flag, value)?;
+    if speed.is_nan() || speed.abs() > MAX_SPEED {
+        return Err(format!(
+            "`{flag}` must be between -{MAX_SPEED} and {MAX_SPEED} radians per frame"
+        ));
+    }
+    Ok(speed)
+}
+
+// Parses three comma-separated numbers, like `0, 0.05, 0`
+fn parse_vector(flag: &str, value: &str) -> Result<[f64; 3], String> {
+    parse::vector(value).map_err(|error| format!("invalid value for `{flag}`: `{value}`, {error}"))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for `{flag}`: `{value}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        Options::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn valid_options() {
+        let options =
+            parse("--still --angle-a 1.5 --angle-b=-0.25 --size 40x12 --shape cube --speed-a 0.1")
+                .unwrap();
+        assert!(options.still);
+        assert_eq!((options.angle_a, options.angle_b), (Some(1.5), Some(-0.25)));
+        assert_eq!(options.size, Some((40, 12)));
+        assert_eq!(options.shape, Some(Shape::Cube));
+        assert_eq!((options.speed_a, options.speed_b), (0.1, DEFAULT_SPEED_B));
+
+        let options = parse("compare --fps 50 --frames 10").unwrap();
+        assert!(options.compare);
+        assert_eq!(options.delay, Duration::from_millis(20));
+        assert_eq!(options.frames, Some(10));
+        assert!(options.draws_torus());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let error = |args: &str| parse(args).unwrap_err();
+        for angle in ["nan", "inf", "-inf"] {
+            assert_eq!(
+                error(&format!("--still --angle-a {angle}")),
+                "`--angle-a` must be a finite number of radians"
+            );
+        }
+        assert_eq!(
+            error("--still --angle-b x"),
+            "invalid value for `--angle-b`: `x`"
+        );
+        assert_eq!(
+            error("--spin 0,nan,0"),
+            "invalid value for `--spin`: `0,nan,0`, not a finite number"
+        );
+        assert_eq!(
+            error("--spin 0,inf,0"),
+            "invalid value for `--spin`: `0,inf,0`, not a finite number"
+        );
+        assert_eq!(
+            error("--spin 0,1"),
+            "invalid value for `--spin`: `0,1`, expected three numbers, for x, y and z"
+        );
+        assert_eq!(
+            error("--speed-a 0.3"),
+            "`--speed-a` must be between -0.25 and 0.25 radians per frame"
+        );
+        assert_eq!(
+            error("--fps 0"),
+            "`--fps` must be a finite number greater than zero"
+        );
+        assert_eq!(
+            error("--size 80"),
+            "invalid value for `--size`: `80`, expected COLSxROWS"
+        );
+        assert_eq!(error("--frames"), "missing value for `--frames`");
+        assert_eq!(error("--wobble"), "unrecognized argument `--wobble`");
+    }
+
+    #[test]
+    fn flags_that_go_together_or_dont() {
+        // The spin stops the tumble, but a speed given with it keeps going whichever
+        // comes first
+        let options = parse("--spin 0,0.05,0").unwrap();
+        assert_eq!(options.spin, [0.0, 0.05, 0.0]);
+        assert_eq!((options.speed_a, options.speed_b), (0.0, 0.0));
+        assert!(!options.draws_torus());
+        for args in [
+            "--spin 0,0.05,0 --speed-b 0.02",
+            "--speed-b 0.02 --spin 0,0.05,0",
+        ] {
+            let options = parse(args).unwrap();
+            assert_eq!((options.speed_a, options.speed_b), (0.0, 0.02), "{args}");
+        }
+
+        let error = |args: &str| parse(args).unwrap_err();
+        assert_eq!(
+            error("--angle-a 1"),
+            "`--angle-a` can only be used with `--still`"
+        );
+        assert_eq!(
+            error("--shape sphere --solid sphere(1)"),
+            "`--shape` and `--solid` can't be used together"
+        );
+        assert_eq!(
+            error("--raymarch --shape klein"),
+            "`--raymarch` can't draw the mobius strip or klein bottle, which aren't solid"
+        );
+        assert_eq!(
+            error("--renderer fixed --shape cube"),
+            "the fixed-point renderer can only draw the torus"
+        );
+        assert_eq!(
+            error("--renderer fixed --spin 0,0.05,0"),
+            "the fixed-point renderer can only draw the torus"
+        );
+        assert_eq!(
+            error("compare --raymarch"),
+            "the fixed-point renderer can't raymarch"
+        );
+    }
+}
@@ -0,0 +1,637 @@
+// cloud.rs
+//
+// Point clouds loaded from PLY and XYZ files, splatted into the frame one pixel per point.
+
+use crate::{
+    color::Rgb,
+    float::{Camera, Real},
+    frame::{Frame, Pixel},
+    mesh::{fit, length, normalize},
+};
+
+/// A cloud of points, each of which can come with a normal and a color.
+///
+/// Points with normals are lit like any other surface. Points without them get brighter
+/// the nearer they are to the viewer, which is usually enough to make out the shape of a
+/// scan.
+#[derive(Clone, Debug)]
+pub struct PointCloud {
+    positions: Vec<[f64; 3]>,
+    normals: Vec<Option<[f64; 3]>>,
+    colors: Vec<Option<Rgb>>,
+}
+
+// The formats the body of a PLY file can come in
+#[derive(Clone, Copy, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+// The types a PLY property can have
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+struct PlyProperty {
+    name: String,
+    scalar: Scalar,
+    // The type of the length that comes before each list, if this property is a list
+    list: Option<Scalar>,
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+// Reads values one at a time out of the body of a PLY file
+enum PlyReader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl PointCloud {
+    /// Parses a point cloud from the text of an XYZ file: one point per line, as its x, y
+    /// and z coordinates separated by spaces or commas.
+    ///
+    /// Three more numbers after the coordinates are taken as a normal if they're all
+    /// between -1 and 1, and as a color from 0 to 255 otherwise. With six more, the normal
+    /// comes first and then the color.
+    pub fn from_xyz(source: &str) -> Result<PointCloud, String> {
+        let mut cloud = PointCloud::empty();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let values = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    // Infinities and NaN would throw off fitting the cloud into view
+                    word.parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                        .ok_or_else(|| format!("line {}: invalid number `{word}`", number + 1))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let vector = |index: usize| [values[index], values[index + 1], values[index + 2]];
+            let (normal, color) = match values.len() {
+                0 => continue,
+                3 => (None, None),
+                6 if values[3..].iter().all(|value| value.abs() <= 1.0) => (Some(vector(3)), None),
+                6 => (None, Some(vector(3))),
+                9 => (Some(vector(3)), Some(vector(6))),
+                count => {
+                    return Err(format!(
+                        "line {}: expected 3, 6 or 9 numbers, found {count}",
+                        number + 1
+                    ))
+                }
+            };
+            cloud.push(
+                vector(0),
+                normal,
+                color.map(|color| color.map(|c| c / 255.0)),
+            );
+        }
+
+        cloud.finish()
+    }
+
+    /// Parses a point cloud from a PLY file, in ASCII or either kind of binary.
+    ///
+    /// The points come from the `vertex` element's `x`, `y` and `z` properties, along with
+    /// `nx`, `ny` and `nz` for normals and `red`, `green` and `blue` for colors if they're
+    /// there. Faces and anything else in the file are ignored.
+    pub fn from_ply(source: &[u8]) -> Result<PointCloud, String> {
+        let header_end = source
+            .windows(10)
+            .position(|window| window == b"end_header")
+            .ok_or_else(|| "not a PLY file: no end_header".to_string())?;
+        let body_start = source[header_end..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(source.len(), |newline| header_end + newline + 1);
+        let header = std::str::from_utf8(&source[..header_end])
+            .map_err(|_| "the PLY header isn't valid text".to_string())?;
+
+        let (format, elements) = parse_ply_header(header)?;
+        let mut reader = match format {
+            PlyFormat::Ascii => PlyReader::Ascii(
+                std::str::from_utf8(&source[body_start..])
+                    .map_err(|_| "the PLY body isn't valid text".to_string())?
+                    .split_whitespace(),
+            ),
+            PlyFormat::LittleEndian | PlyFormat::BigEndian => PlyReader::Binary {
+                bytes: &source[body_start..],
+                big_endian: format == PlyFormat::BigEndian,
+            },
+        };
+
+        let mut cloud = PointCloud::empty();
+        for element in &elements {
+            let is_vertex = element.name == "vertex";
+            let index_of = |names: &[&str]| {
+                element
+                    .properties
+                    .iter()
+                    .position(|property| names.contains(&property.name.as_str()))
+            };
+            let position = [&["x"][..], &["y"], &["z"]].map(index_of);
+            let normal = [&["nx"][..], &["ny"], &["nz"]].map(index_of);
+            let color = [
+                &["red", "r", "diffuse_red"][..],
+                &["green", "g", "diffuse_green"],
+                &["blue", "b", "diffuse_blue"],
+            ]
+            .map(index_of);
+
+            // Colors stored as floating point run from 0 to 1, and as integers from 0 to 255
+            let color_scale = match color[0] {
+                Some(red) if element.properties[red].scalar.is_float() => 1.0,
+                _ => 255.0,
+            };
+
+            for index in 0..element.count {
+                let mut values = Vec::with_capacity(element.properties.len());
+                for property in &element.properties {
+                    match property.list {
+                        // Lists are only ever faces and the like, which can be skipped
+                        Some(length) => {
+                            for _ in 0..reader.read(length)? as usize {
+                                reader.read(property.scalar)?;
+                            }
+                            values.push(0.0);
+                        }
+                        None => values.push(reader.read(property.scalar)?),
+                    }
+                }
+                if !is_vertex {
+                    continue;
+                }
+
+                let vector = |indices: [Option<usize>; 3]| match indices {
+                    [Some(x), Some(y), Some(z)] => Some([values[x], values[y], values[z]]),
+                    _ => None,
+                };
+                let position = vector(position)
+                    .ok_or_else(|| "the vertices need x, y and z properties".to_string())?;
+                let (normal, color) = (vector(normal), vector(color));
+                if [Some(position), normal, color]
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .any(|value| !value.is_finite())
+                {
+                    return Err(format!(
+                        "vertex {}: a value isn't a finite number",
+                        index + 1
+                    ));
+                }
+                cloud.push(
+                    position,
+                    normal,
+                    color.map(|color| color.map(|c| c / color_scale)),
+                );
+            }
+
+            // There's nothing after the vertices that a point cloud needs
+            if is_vertex {
+                break;
+            }
+        }
+
+        cloud.finish()
+    }
+
+    /// Moves and scales the cloud so it's centered on the origin and spins in the same
+    /// space as the donut, whatever units it was captured in.
+    pub fn fitted(mut self) -> Self {
+        fit(&mut self.positions);
+        self
+    }
+
+    /// The number of points in the cloud.
+    pub fn points(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn empty() -> Self {
+        PointCloud {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            colors: Vec::new(),
+        }
+    }
+
+    // Adds a point, with its color given as red, green and blue from 0.0 to 1.0
+    fn push(&mut self, position: [f64; 3], normal: Option<[f64; 3]>, color: Option<[f64; 3]>) {
+        self.positions.push(position);
+        self.normals.push(normal.map(normalize));
+        self.colors.push(color.map(|color| {
+            let [red, green, blue] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            Rgb(red, green, blue)
+        }));
+    }
+
+    fn finish(self) -> Result<Self, String> {
+        if self.positions.is_empty() {
+            return Err("no points found".to_string());
+        }
+        Ok(self)
+    }
+
+    /// Draws every point of the cloud into `frame`, one pixel each.
+    pub(crate) fn splat<T: Real>(&self, camera: &Camera<T>, frame: &mut Frame) {
+        // Shading by depth goes from brightest at the nearest the cloud could possibly get
+        // to darkest at the farthest, so it doesn't flicker as the cloud turns
+        let center = camera.view([0.0; 3])[2].to_f64();
+        let radius = self
+            .positions
+            .iter()
+            .map(|&position| length(position))
+            .fold(f64::EPSILON, f64::max)
+            * camera.size().to_f64();
+
+        for ((&position, normal), &color) in
+            self.positions.iter().zip(&self.normals).zip(&self.colors)
+        {
+            let point = camera.view(position);
+            let depth = point[2].to_f64();
+            if depth <= 0.0 {
+                continue;
+            }
+
+            let luminance = match normal {
+                Some(normal) => {
+                    // There's no telling which way a lone point's normal ought to face, so
+                    // light whichever side is in view
+                    let mut normal = camera.rotate(normal.map(T::from_f64));
+                    if camera.faces_away(point, normal) {
+                        normal = normal.map(|n| -n);
+                    }
+                    camera.luminance(normal).to_f64()
+                }
+                None => 0.5 + (center - depth) / (2.0 * radius),
+            };
+
+            if let Some((x, y)) = camera.pixel(point) {
+                frame.plot_pixel(
+                    x,
+                    y,
+                    Pixel {
+                        luminance: luminance as f32,
+                        depth: depth as f32,
+                        color: color.or(camera.color()),
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Scalar::F32 | Scalar::F64)
+    }
+}
+
+impl PlyReader<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        match self {
+            PlyReader::Ascii(words) => {
+                let word = words
+                    .next()
+                    .ok_or_else(|| "the PLY file ends early".to_string())?;
+                word.parse()
+                    .map_err(|_| format!("invalid number `{word}` in the PLY file"))
+            }
+            PlyReader::Binary { bytes, big_endian } => {
+                let size = scalar.size();
+                if bytes.len() < size {
+                    return Err("the PLY file ends early".to_string());
+                }
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+
+                // Put the bytes in little-endian order, padded out to eight
+                let mut buffer = [0; 8];
+                buffer[..size].copy_from_slice(value);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+                let [a, b, c, d, ..] = buffer;
+                Ok(match scalar {
+                    Scalar::I8 => f64::from(a as i8),
+                    Scalar::U8 => f64::from(a),
+                    Scalar::I16 => f64::from(i16::from_le_bytes([a, b])),
+                    Scalar::U16 => f64::from(u16::from_le_bytes([a, b])),
+                    Scalar::I32 => f64::from(i32::from_le_bytes([a, b, c, d])),
+                    Scalar::U32 => f64::from(u32::from_le_bytes([a, b, c, d])),
+                    Scalar::F32 => f64::from(f32::from_le_bytes([a, b, c, d])),
+                    Scalar::F64 => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+}
+
+// Reads the format and the elements out of a PLY header
+fn parse_ply_header(header: &str) -> Result<(PlyFormat, Vec<PlyElement>), String> {
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err("not a PLY file: it should start with `ply`".to_string());
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["format", name, _] => {
+                format = Some(match name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::LittleEndian,
+                    "binary_big_endian" => PlyFormat::BigEndian,
+                    _ => return Err(format!("unknown PLY format `{name}`")),
+                });
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("invalid count for element `{name}`: `{count}`"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", length, scalar, name] => {
+                let property = PlyProperty {
+                    name: name.to_string(),
+                    scalar: parse_scalar(scalar)?,
+                    list: Some(parse_scalar(length)?),
+                };
+                add_property(&mut elements, property)?;
+            }
+            ["property", scalar, name] => {
+                let property = PlyProperty {
+                    name: name.to_string(),
+                    scalar: parse_scalar(scalar)?,
+                    list: None,
+                };
+                add_property(&mut elements, property)?;
+            }
+            // Comments, obj_info and blank lines
+            _ => {}
+        }
+    }
+
+    let format = format.ok_or_else(|| "the PLY header has no format".to_string())?;
+    Ok((format, elements))
+}
+
+fn parse_scalar(name: &str) -> Result<Scalar, String> {
+    Scalar::parse(name).ok_or_else(|| format!("unknown PLY property type `{name}`"))
+}
+
+fn add_property(elements: &mut [PlyElement], property: PlyProperty) -> Result<(), String> {
+    elements
+        .last_mut()
+        .ok_or_else(|| format!("property `{}` comes before any element", property.name))?
+        .properties
+        .push(property);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ply_with_positions_only() {
+        let cloud = PointCloud::from_ply(
+            b"ply\nformat ascii 1.0\ncomment made by hand\n\
+              element vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+              end_header\n1 2 3\n-4 5.5 6\n",
+        )
+        .unwrap();
+        assert_eq!(cloud.positions, [[1.0, 2.0, 3.0], [-4.0, 5.5, 6.0]]);
+        assert_eq!(cloud.normals, [None, None]);
+        assert_eq!(cloud.colors, [None, None]);
+    }
+
+    #[test]
+    fn ply_with_normals_and_colors() {
+        let cloud = PointCloud::from_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\n\
+              property uchar red\nproperty uchar green\nproperty uchar blue\n\
+              property float x\nproperty float y\nproperty float z\n\
+              property float nx\nproperty float ny\nproperty float nz\n\
+              end_header\n255 128 0 1 2 3 0 0 2\n",
+        )
+        .unwrap();
+        assert_eq!(cloud.positions, [[1.0, 2.0, 3.0]]);
+        assert_eq!(cloud.normals, [Some([0.0, 0.0, 1.0])]);
+        assert_eq!(cloud.colors, [Some(Rgb(255, 128, 0))]);
+
+        // Colors stored as floats go from 0 to 1
+        let cloud = PointCloud::from_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\n\
+              property float x\nproperty float y\nproperty float z\n\
+              property float r\nproperty float g\nproperty float b\n\
+              end_header\n0 0 0 1 0.5 0\n",
+        )
+        .unwrap();
+        assert_eq!(cloud.normals, [None]);
+        assert_eq!(cloud.colors, [Some(Rgb(255, 128, 0))]);
+    }
+
+    #[test]
+    fn ply_binary_with_faces() {
+        // The same two points and a face, in either byte order
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut ply = format!(
+                "ply\nformat {format} 1.0\nelement vertex 2\n\
+                 property double x\nproperty double y\nproperty double z\n\
+                 property short intensity\n\
+                 element face 1\nproperty list uchar int vertex_indices\nend_header\n"
+            )
+            .into_bytes();
+            for point in [[1.0f64, 2.0, 3.0], [-1.0, -2.0, -3.0]] {
+                for n in point {
+                    ply.extend(if big_endian {
+                        n.to_be_bytes()
+                    } else {
+                        n.to_le_bytes()
+                    });
+                }
+                ply.extend(if big_endian {
+                    (-7i16).to_be_bytes()
+                } else {
+                    (-7i16).to_le_bytes()
+                });
+            }
+            ply.push(3);
+            for index in [0i32, 1, 0] {
+                ply.extend(if big_endian {
+                    index.to_be_bytes()
+                } else {
+                    index.to_le_bytes()
+                });
+            }
+
+            let cloud = PointCloud::from_ply(&ply).unwrap();
+            assert_eq!(cloud.positions, [[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
+
+            // Truncated in the middle of the vertices
+            assert_eq!(
+                PointCloud::from_ply(&ply[..ply.len() - 40]).unwrap_err(),
+                "the PLY file ends early"
+            );
+        }
+    }
+
+    #[test]
+    fn ply_binary_values_must_be_finite() {
+        for bad in [f32::NAN, f32::INFINITY] {
+            let mut ply = b"ply\nformat binary_little_endian 1.0\nelement vertex 1\n\
+                            property float x\nproperty float y\nproperty float z\n\
+                            property float nx\nproperty float ny\nproperty float nz\nend_header\n"
+                .to_vec();
+            for n in [0.0, 0.0, 0.0, 0.0, bad, 0.0] {
+                ply.extend(n.to_le_bytes());
+            }
+            assert_eq!(
+                PointCloud::from_ply(&ply).unwrap_err(),
+                "vertex 1: a value isn't a finite number"
+            );
+        }
+    }
+
+    #[test]
+    fn ply_errors() {
+        let error = |source: &str| PointCloud::from_ply(source.as_bytes()).unwrap_err();
+        assert_eq!(
+            error("ply\nformat ascii 1.0\n"),
+            "not a PLY file: no end_header"
+        );
+        assert_eq!(
+            error("obj\nend_header\n"),
+            "not a PLY file: it should start with `ply`"
+        );
+        assert_eq!(
+            error("ply\nformat utf8 1.0\nend_header\n"),
+            "unknown PLY format `utf8`"
+        );
+        assert_eq!(error("ply\nend_header\n"), "the PLY header has no format");
+        assert_eq!(
+            error("ply\nformat ascii 1.0\nproperty float x\nend_header\n"),
+            "property `x` comes before any element"
+        );
+        assert_eq!(
+            error("ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n"),
+            "unknown PLY property type `quad`"
+        );
+        assert_eq!(
+            error("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n"),
+            "the vertices need x, y and z properties"
+        );
+        assert_eq!(
+            error(
+                "ply\nformat ascii 1.0\nelement vertex 2\n\
+                 property float x\nproperty float y\nproperty float z\nend_header\n1 2 3\n"
+            ),
+            "the PLY file ends early"
+        );
+        assert_eq!(
+            error(
+                "ply\nformat ascii 1.0\nelement vertex 2\n\
+                 property float x\nproperty float y\nproperty float z\nend_header\n\
+                 1 2 3\n1 nan 3\n"
+            ),
+            "vertex 2: a value isn't a finite number"
+        );
+        assert_eq!(
+            error("ply\nformat ascii 1.0\nelement vertex 0\nend_header\n"),
+            "no points found"
+        );
+    }
+
+    #[test]
+    fn xyz_columns() {
+        let cloud = PointCloud::from_xyz(
+            "# a comment\n\
+             1 2 3\n\
+             1,2,3, 0,0,-1\n\
+             1 2 3 255 0 0\n\
+             1 2 3 0 1 0 0 0 255\n\
+             \n",
+        )
+        .unwrap();
+        assert_eq!(cloud.positions, [[1.0, 2.0, 3.0]; 4]);
+        assert_eq!(
+            cloud.normals,
+            [None, Some([0.0, 0.0, -1.0]), None, Some([0.0, 1.0, 0.0])]
+        );
+        assert_eq!(
+            cloud.colors,
+            [None, None, Some(Rgb(255, 0, 0)), Some(Rgb(0, 0, 255))]
+        );
+    }
+
+    #[test]
+    fn xyz_errors() {
+        assert_eq!(
+            PointCloud::from_xyz("1 2 3\n1 2\n").unwrap_err(),
+            "line 2: expected 3, 6 or 9 numbers, found 2"
+        );
+        assert_eq!(
+            PointCloud::from_xyz("1 2 x\n").unwrap_err(),
+            "line 1: invalid number `x`"
+        );
+        assert_eq!(
+            PointCloud::from_xyz("1 2 3\n1 NaN 3\n").unwrap_err(),
+            "line 2: invalid number `NaN`"
+        );
+        assert_eq!(
+            PointCloud::from_xyz("1 2 3 0 0 -inf\n").unwrap_err(),
+            "line 1: invalid number `-inf`"
+        );
+        assert_eq!(
+            PointCloud::from_xyz("# nothing\n").unwrap_err(),
+            "no points found"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+// color.rs
+//
+// Colors, gradients, and the ANSI escape codes for getting them onto the terminal.
+
+use std::{fmt, str::FromStr};
+
+/// A color given by its red, green and blue components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    // Blends between two colors, with `t` running from 0.0 (all `self`) to 1.0 (all `other`)
+    fn lerp(self, other: Rgb, t: f32) -> Rgb {
+        let channel =
+            |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+        Rgb(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+        )
+    }
+
+    /// Scales the brightness of the color by `factor`, between 0.0 and 1.0.
+    pub fn dim(self, factor: f32) -> Rgb {
+        Rgb(0, 0, 0).lerp(self, factor.clamp(0.0, 1.0))
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    /// Parses a hex color, either as `#rrggbb` or the shorthand `#rgb`. The `#` is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim().trim_start_matches('#');
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("invalid color `{s}`"))?;
+
+        match digits[..] {
+            [r, g, b] => Ok(Rgb(r * 17, g * 17, b * 17)),
+            [r1, r2, g1, g2, b1, b2] => Ok(Rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+            _ => Err(format!("invalid color `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// A run of colors evenly spaced from 0.0 to 1.0, blended together in between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Rgb>,
+}
+
+impl Gradient {
+    /// Creates a gradient through the given colors. Returns `None` if there aren't any.
+    pub fn new(stops: Vec<Rgb>) -> Option<Gradient> {
+        (!stops.is_empty()).then_some(Gradient { stops })
+    }
+
+    /// Returns the color at `t`, between 0.0 and 1.0.
+    pub fn sample(&self, t: f32) -> Rgb {
+        let position = t.clamp(0.0, 1.0) * (self.stops.len() - 1) as f32;
+        let index = (position as usize).min(self.stops.len() - 1);
+        let next = (index + 1).min(self.stops.len() - 1);
+        self.stops[index].lerp(self.stops[next], position - index as f32)
+    }
+}
+
+impl Default for Gradient {
+    /// From the shadowed dough up to the frosting on top.
+    fn default() -> Self {
+        Gradient {
+            stops: vec![
+                Rgb(0x3b, 0x1d, 0x0b),
+                Rgb(0x8a, 0x4b, 0x1c),
+                Rgb(0xd4, 0x93, 0x4a),
+                Rgb(0xf5, 0xc9, 0x8e),
+                Rgb(0xff, 0xb3, 0xd1),
+            ],
+        }
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = String;
+
+    /// Parses a comma-separated list of hex colors, like `#000,#ff8800,#fff`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stops = s.split(',').map(str::parse).collect::<Result<_, _>>()?;
+        Gradient::new(stops).ok_or_else(|| "a gradient needs at least one color".to_string())
+    }
+}
+
+/// How many colors the terminal can show, which decides the escape codes used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Plain text, without any escape codes.
+    #[default]
+    None,
+    /// The 16 standard ANSI colors.
+    Ansi16,
+    /// The 256-color xterm palette.
+    Ansi256,
+    /// Full 24-bit color.
+    TrueColor,
+}
+
+// The usual xterm values for the 16 standard colors
+const ANSI_16: [Rgb; 16] = [
+    Rgb(0, 0, 0),
+    Rgb(205, 0, 0),
+    Rgb(0, 205, 0),
+    Rgb(205, 205, 0),
+    Rgb(0, 0, 238),
+    Rgb(205, 0, 205),
+    Rgb(0, 205, 205),
+    Rgb(229, 229, 229),
+    Rgb(127, 127, 127),
+    Rgb(255, 0, 0),
+    Rgb(0, 255, 0),
+    Rgb(255, 255, 0),
+    Rgb(92, 92, 255),
+    Rgb(255, 0, 255),
+    Rgb(0, 255, 255),
+    Rgb(255, 255, 255),
+];
+
+impl ColorMode {
+    /// Returns the escape code that sets the foreground color to (roughly) `color`, or
+    /// `None` in [`ColorMode::None`].
+    pub fn foreground(self, color: Rgb) -> Option<String> {
+        self.sgr(color, false)
+    }
+
+    /// Returns the escape code that sets the background color to (roughly) `color`, or
+    /// `None` in [`ColorMode::None`].
+    pub fn background(self, color: Rgb) -> Option<String> {
+        self.sgr(color, true)
+    }
+
+    fn sgr(self, Rgb(r, g, b): Rgb, background: bool) -> Option<String> {
+        let layer = if background { 48 } else { 38 };
+        match self {
+            ColorMode::None => None,
+            ColorMode::TrueColor => Some(format!("\x1b[{layer};2;{r};{g};{b}m")),
+            ColorMode::Ansi256 => {
+                // The 6x6x6 color cube starts at index 16
+                let level = |channel: u8| (u16::from(channel) * 5 + 127) / 255;
+                let index = 16 + 36 * level(r) + 6 * level(g) + level(b);
+                Some(format!("\x1b[{layer};5;{index}m"))
+            }
+            ColorMode::Ansi16 => {
+                let distance = |other: &Rgb| {
+                    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+                    d(r, other.0) + d(g, other.1) + d(b, other.2)
+                };
+                let index = (0..ANSI_16.len())
+                    .min_by_key(|&index| distance(&ANSI_16[index]))
+                    .unwrap_or(0);
+                // Codes 30-37 are the normal colors and 90-97 the bright ones,
+                // with 10 more for the backgrounds
+                let base = if index < 8 { 30 } else { 90 - 8 };
+                Some(format!("\x1b[{}m", base + index + layer as usize - 38))
+            }
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ColorMode::None),
+            "16" => Ok(ColorMode::Ansi16),
+            "256" => Ok(ColorMode::Ansi256),
+            "truecolor" | "24bit" => Ok(ColorMode::TrueColor),
+            _ => Err(format!(
+                "unknown color mode `{s}`, expected one of truecolor, 256, 16 or none"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_codes() {
+        let orange = Rgb(255, 128, 0);
+        assert_eq!(ColorMode::None.foreground(orange), None);
+        assert_eq!(ColorMode::None.background(orange), None);
+
+        assert_eq!(
+            ColorMode::TrueColor.foreground(orange).unwrap(),
+            "\x1b[38;2;255;128;0m"
+        );
+        assert_eq!(
+            ColorMode::TrueColor.background(orange).unwrap(),
+            "\x1b[48;2;255;128;0m"
+        );
+
+        // Each channel rounds to one of six levels in the color cube
+        assert_eq!(
+            ColorMode::Ansi256.foreground(orange).unwrap(),
+            "\x1b[38;5;214m"
+        );
+        assert_eq!(
+            ColorMode::Ansi256.background(orange).unwrap(),
+            "\x1b[48;5;214m"
+        );
+        assert_eq!(
+            ColorMode::Ansi256.foreground(Rgb(0, 0, 0)).unwrap(),
+            "\x1b[38;5;16m"
+        );
+        assert_eq!(
+            ColorMode::Ansi256.foreground(Rgb(255, 255, 255)).unwrap(),
+            "\x1b[38;5;231m"
+        );
+
+        // The nearest of the 16, from the normal ones or the bright ones
+        assert_eq!(
+            ColorMode::Ansi16.foreground(Rgb(0, 0, 0)).unwrap(),
+            "\x1b[30m"
+        );
+        assert_eq!(
+            ColorMode::Ansi16.background(Rgb(0, 0, 0)).unwrap(),
+            "\x1b[40m"
+        );
+        assert_eq!(
+            ColorMode::Ansi16.foreground(Rgb(200, 10, 0)).unwrap(),
+            "\x1b[31m"
+        );
+        assert_eq!(
+            ColorMode::Ansi16.foreground(Rgb(250, 10, 10)).unwrap(),
+            "\x1b[91m"
+        );
+        assert_eq!(
+            ColorMode::Ansi16.background(Rgb(250, 10, 10)).unwrap(),
+            "\x1b[101m"
+        );
+        assert_eq!(
+            ColorMode::Ansi16.foreground(Rgb(255, 255, 255)).unwrap(),
+            "\x1b[97m"
+        );
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!("#ff8800".parse(), Ok(Rgb(255, 136, 0)));
+        assert_eq!("f80".parse(), Ok(Rgb(255, 136, 0)));
+        assert_eq!(" #3B1D0B ".parse(), Ok(Rgb(0x3b, 0x1d, 0x0b)));
+        assert_eq!(
+            "#ff88".parse::<Rgb>(),
+            Err("invalid color `#ff88`".to_string())
+        );
+        assert_eq!(
+            "#ggg".parse::<Rgb>(),
+            Err("invalid color `#ggg`".to_string())
+        );
+        assert_eq!(Rgb(255, 136, 0).to_string(), "#ff8800");
+    }
+
+    #[test]
+    fn gradients() {
+        let gradient: Gradient = "#000,#fff".parse().unwrap();
+        assert_eq!(gradient.sample(0.0), Rgb(0, 0, 0));
+        assert_eq!(gradient.sample(0.5), Rgb(128, 128, 128));
+        assert_eq!(gradient.sample(1.0), Rgb(255, 255, 255));
+        assert_eq!(gradient.sample(-1.0), Rgb(0, 0, 0));
+        assert_eq!(gradient.sample(2.0), Rgb(255, 255, 255));
+
+        let gradient: Gradient = "#f00,#0f0,#00f".parse().unwrap();
+        assert_eq!(gradient.sample(0.5), Rgb(0, 255, 0));
+        assert_eq!(gradient.sample(0.75), Rgb(0, 128, 128));
+
+        let gradient: Gradient = "#123456".parse().unwrap();
+        assert_eq!(gradient.sample(0.7), Rgb(0x12, 0x34, 0x56));
+
+        assert_eq!(Rgb(200, 100, 0).dim(0.5), Rgb(100, 50, 0));
+        assert_eq!(
+            "#000,,#fff".parse::<Gradient>(),
+            Err("invalid color ``".to_string())
+        );
+    }
+
+    #[test]
+    fn color_modes() {
+        assert_eq!("truecolor".parse(), Ok(ColorMode::TrueColor));
+        assert_eq!("24bit".parse(), Ok(ColorMode::TrueColor));
+        assert_eq!("256".parse(), Ok(ColorMode::Ansi256));
+        assert_eq!("16".parse(), Ok(ColorMode::Ansi16));
+        assert_eq!("none".parse(), Ok(ColorMode::None));
+        assert_eq!(
+            "8".parse::<ColorMode>(),
+            Err("unknown color mode `8`, expected one of truecolor, 256, 16 or none".to_string())
+        );
+    }
+}
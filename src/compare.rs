@@ -0,0 +1,200 @@
+// compare.rs
+//
+// Measures how far apart two renderers' frames are, cell by cell.
+
+use std::fmt;
+
+use crate::{frame::Frame, ramp::Ramp};
+
+// The upper bounds of each bucket of the luminance error histogram, with anything
+// bigger landing in the last one
+const LUMINANCE_BUCKETS: [f32; 5] = [0.01, 0.02, 0.05, 0.1, 0.2];
+
+// Depth differences smaller than this are put down to the fixed-point renderer storing
+// depth in 32nds of a unit
+const DEPTH_TOLERANCE: f32 = 1.0 / 32.0;
+
+/// Running totals of the differences between pairs of frames, built up one pair at a time.
+///
+/// Each pair is a frame from the renderer under test and one from a reference renderer,
+/// rendered at the same angles and size.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    ramp: Ramp,
+    frames: usize,
+    cells: usize,
+    missing: usize,
+    extra: usize,
+    glyph_mismatches: usize,
+    luminance_histogram: [usize; LUMINANCE_BUCKETS.len() + 1],
+    luminance_error_total: f64,
+    luminance_error_max: f32,
+    depth_mismatches: usize,
+    depth_error_total: f64,
+    depth_error_max: f32,
+    worst_frame: Option<(usize, usize)>,
+}
+
+impl Comparison {
+    /// Starts a comparison that decides glyph mismatches with `ramp`.
+    pub fn new(ramp: Ramp) -> Self {
+        Comparison {
+            ramp,
+            frames: 0,
+            cells: 0,
+            missing: 0,
+            extra: 0,
+            glyph_mismatches: 0,
+            luminance_histogram: [0; LUMINANCE_BUCKETS.len() + 1],
+            luminance_error_total: 0.0,
+            luminance_error_max: 0.0,
+            depth_mismatches: 0,
+            depth_error_total: 0.0,
+            depth_error_max: 0.0,
+            worst_frame: None,
+        }
+    }
+
+    /// Adds the differences between `frame` and `reference` to the totals. Frames of
+    /// different sizes are compared over the area they share.
+    pub fn add(&mut self, frame: &Frame, reference: &Frame) {
+        let width = frame.width().min(reference.width());
+        let height = frame.height().min(reference.height());
+        let mut mismatches = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (pixel, expected) = match (frame.pixel(x, y), reference.pixel(x, y)) {
+                    (None, None) => continue,
+                    (None, Some(_)) => {
+                        self.missing += 1;
+                        mismatches += 1;
+                        continue;
+                    }
+                    (Some(_), None) => {
+                        self.extra += 1;
+                        mismatches += 1;
+                        continue;
+                    }
+                    (Some(pixel), Some(expected)) => (pixel, expected),
+                };
+                self.cells += 1;
+
+                if self.ramp.glyph(pixel.luminance) != self.ramp.glyph(expected.luminance) {
+                    self.glyph_mismatches += 1;
+                    mismatches += 1;
+                }
+
+                let luminance_error = (pixel.luminance - expected.luminance).abs();
+                let bucket = LUMINANCE_BUCKETS
+                    .iter()
+                    .position(|&bound| luminance_error < bound)
+                    .unwrap_or(LUMINANCE_BUCKETS.len());
+                self.luminance_histogram[bucket] += 1;
+                self.luminance_error_total += f64::from(luminance_error);
+                self.luminance_error_max = self.luminance_error_max.max(luminance_error);
+
+                let depth_error = (pixel.depth - expected.depth).abs();
+                if depth_error > DEPTH_TOLERANCE {
+                    self.depth_mismatches += 1;
+                }
+                self.depth_error_total += f64::from(depth_error);
+                self.depth_error_max = self.depth_error_max.max(depth_error);
+            }
+        }
+
+        if self.worst_frame.is_none_or(|(_, worst)| mismatches > worst) {
+            self.worst_frame = Some((self.frames, mismatches));
+        }
+        self.frames += 1;
+    }
+
+    /// The number of pairs of frames compared so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// The total number of cells that differ in coverage or glyph between the pairs of frames.
+    pub fn mismatches(&self) -> usize {
+        self.missing + self.extra + self.glyph_mismatches
+    }
+}
+
+// Prints a report of everything measured, as a percentage of the cells involved where
+// that makes sense
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                100.0 * count as f64 / total as f64
+            }
+        };
+        let covered = self.cells + self.missing + self.extra;
+
+        writeln!(f, "Frames compared:      {}", self.frames)?;
+        writeln!(f, "Cells covered:        {covered}")?;
+        writeln!(
+            f,
+            "  missing:            {} ({:.2}%)",
+            self.missing,
+            percent(self.missing, covered)
+        )?;
+        writeln!(
+            f,
+            "  extra:              {} ({:.2}%)",
+            self.extra,
+            percent(self.extra, covered)
+        )?;
+        writeln!(
+            f,
+            "Glyph mismatches:     {} ({:.2}% of cells covered by both)",
+            self.glyph_mismatches,
+            percent(self.glyph_mismatches, self.cells)
+        )?;
+        if let Some((frame, mismatches)) = self.worst_frame {
+            writeln!(
+                f,
+                "Worst frame:          #{frame}, {mismatches} cells differ"
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Luminance error:")?;
+        let mut lower = 0.0;
+        for (index, count) in self.luminance_histogram.iter().enumerate() {
+            let label = match LUMINANCE_BUCKETS.get(index) {
+                Some(upper) => format!("{lower:.2} - {upper:.2}"),
+                None => format!("{lower:.2} +"),
+            };
+            let share = percent(*count, self.cells);
+            // One # for every 2% of cells, so a full bar is 50 characters wide
+            let bar = "#".repeat((share / 2.0).round() as usize);
+            writeln!(f, "  {label:<12} {count:>9} {share:>6.2}% {bar}")?;
+            lower = LUMINANCE_BUCKETS.get(index).copied().unwrap_or(lower);
+        }
+        let cells = self.cells.max(1) as f64;
+        writeln!(
+            f,
+            "  mean {:.4}, max {:.4}",
+            self.luminance_error_total / cells,
+            self.luminance_error_max
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "Depth error:")?;
+        writeln!(
+            f,
+            "  beyond {DEPTH_TOLERANCE:.4}: {} ({:.2}%)",
+            self.depth_mismatches,
+            percent(self.depth_mismatches, self.cells)
+        )?;
+        write!(
+            f,
+            "  mean {:.4}, max {:.4}",
+            self.depth_error_total / cells,
+            self.depth_error_max
+        )
+    }
+}
@@ -0,0 +1,450 @@
+// expr.rs
+//
+// A small parser and evaluator for math expressions, for drawing surfaces written out as
+// formulas rather than compiled in.
+
+use std::{
+    f64::consts::{E, PI, TAU},
+    fmt,
+    ops::Sub,
+};
+
+/// A math expression, like `(2 + cos v) * cos u`, parsed once so it can be evaluated
+/// quickly over and over.
+///
+/// Expressions have the usual `+`, `-`, `*`, `/` and `^`, parentheses, and numbers, which
+/// can be multiplied just by writing them next to each other, as in `2pi` or `3 sin u`.
+/// The constants `pi`, `tau` and `e` are built in, and so are these functions:
+///
+/// - `sin`, `cos`, `tan`, `asin`, `acos`, `atan`, `sinh`, `cosh` and `tanh`
+/// - `sqrt`, `cbrt`, `abs`, `exp`, `ln`, `log` (base 10), `floor`, `ceil`, `round` and
+///   `sign`
+/// - `atan2(y, x)`, `min(a, b)`, `max(a, b)`, `pow(a, b)`, `hypot(a, b)` and `mod(a, b)`
+///
+/// Functions of one thing don't need parentheses around a single number or variable, so
+/// `cos v` is the same as `cos(v)`. Anything longer does need them: `cos 2v` is
+/// `cos(2) * v`.
+#[derive(Clone, Debug)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    /// Parses `text`, which can use any of the names in `variables`. Their values are
+    /// passed to [`evaluate`](Expression::evaluate) in the same order.
+    pub fn parse(text: &str, variables: &[&str]) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            variables,
+        };
+        let root = parser.sum()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(Expression { root }),
+            Some(token) => Err(format!("unexpected {token}")),
+        }
+    }
+
+    /// Works out the value of the expression, with each of its variables set to the
+    /// matching one of `values`.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer values than variables the expression was parsed with.
+    pub fn evaluate(&self, values: &[f64]) -> f64 {
+        self.root.evaluate(values)
+    }
+}
+
+impl Sub for Expression {
+    type Output = Expression;
+
+    /// An expression for the difference between two others, which share the same
+    /// variables.
+    fn sub(self, other: Expression) -> Expression {
+        Expression {
+            root: Node::Subtract(Box::new(self.root), Box::new(other.root)),
+        }
+    }
+}
+
+// The built-in functions of one and two numbers
+type Function = fn(f64) -> f64;
+type Function2 = fn(f64, f64) -> f64;
+
+// One piece of a parsed expression
+#[derive(Clone, Debug)]
+enum Node {
+    Number(f64),
+    Variable(usize),
+    Negate(Box<Node>),
+    Add(Box<Node>, Box<Node>),
+    Subtract(Box<Node>, Box<Node>),
+    Multiply(Box<Node>, Box<Node>),
+    Divide(Box<Node>, Box<Node>),
+    Power(Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+    Call2(Function2, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn evaluate(&self, values: &[f64]) -> f64 {
+        match self {
+            Node::Number(number) => *number,
+            Node::Variable(index) => values[*index],
+            Node::Negate(a) => -a.evaluate(values),
+            Node::Add(a, b) => a.evaluate(values) + b.evaluate(values),
+            Node::Subtract(a, b) => a.evaluate(values) - b.evaluate(values),
+            Node::Multiply(a, b) => a.evaluate(values) * b.evaluate(values),
+            Node::Divide(a, b) => a.evaluate(values) / b.evaluate(values),
+            Node::Power(a, b) => a.evaluate(values).powf(b.evaluate(values)),
+            Node::Call(function, a) => function(a.evaluate(values)),
+            Node::Call2(function, a, b) => function(a.evaluate(values), b.evaluate(values)),
+        }
+    }
+}
+
+const CONSTANTS: [(&str, f64); 3] = [("pi", PI), ("tau", TAU), ("e", E)];
+
+const FUNCTIONS: [(&str, Function); 19] = [
+    ("sin", f64::sin),
+    ("cos", f64::cos),
+    ("tan", f64::tan),
+    ("asin", f64::asin),
+    ("acos", f64::acos),
+    ("atan", f64::atan),
+    ("sinh", f64::sinh),
+    ("cosh", f64::cosh),
+    ("tanh", f64::tanh),
+    ("sqrt", f64::sqrt),
+    ("cbrt", f64::cbrt),
+    ("abs", f64::abs),
+    ("exp", f64::exp),
+    ("ln", f64::ln),
+    ("log", f64::log10),
+    ("floor", f64::floor),
+    ("ceil", f64::ceil),
+    ("round", f64::round),
+    ("sign", sign),
+];
+
+const FUNCTIONS_2: [(&str, Function2); 6] = [
+    ("atan2", f64::atan2),
+    ("min", f64::min),
+    ("max", f64::max),
+    ("pow", f64::powf),
+    ("hypot", f64::hypot),
+    ("mod", f64::rem_euclid),
+];
+
+// Like `f64::signum`, but zero for zero
+fn sign(x: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x.signum()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "`{number}`"),
+            Token::Name(name) => write!(f, "`{name}`"),
+            Token::Symbol(symbol) => write!(f, "`{symbol}`"),
+        }
+    }
+}
+
+// Splits an expression into numbers, names and symbols
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                // Take an exponent along with the number, like the `e-3` of `1e-3`, but
+                // leave the `e` of `2e` or `2exp(u)` for a name of its own
+                let exponent = (c == 'e' || c == 'E')
+                    && text[i + 1..]
+                        .trim_start_matches(['+', '-'])
+                        .starts_with(|c: char| c.is_ascii_digit());
+                if c.is_ascii_digit() || c == '.' {
+                    chars.next();
+                } else if exponent {
+                    chars.next();
+                    chars.next_if(|&(_, c)| c == '+' || c == '-');
+                } else {
+                    break;
+                }
+                end = chars.peek().map_or(text.len(), |&(i, _)| i);
+            }
+            let number = &text[start..end];
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| format!("invalid number `{number}`"))?,
+            ));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_') {
+                name.push(c);
+            }
+            tokens.push(Token::Name(name.to_lowercase()));
+        } else if "+-*/^(),".contains(c) {
+            chars.next();
+            tokens.push(Token::Symbol(c));
+        } else {
+            return Err(format!("unexpected `{c}`"));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Builds up an expression from its tokens, one level of precedence per method
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    // Takes the next token if it's `symbol`
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            return Ok(());
+        }
+        Err(match self.peek() {
+            Some(token) => format!("expected `{symbol}`, found {token}"),
+            None => format!("expected `{symbol}`"),
+        })
+    }
+
+    // Terms added or subtracted
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.product()?;
+        loop {
+            if self.eat('+') {
+                node = Node::Add(Box::new(node), Box::new(self.product()?));
+            } else if self.eat('-') {
+                node = Node::Subtract(Box::new(node), Box::new(self.product()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    // Factors multiplied or divided, including ones just written next to each other
+    fn product(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            if self.eat('*') {
+                node = Node::Multiply(Box::new(node), Box::new(self.unary()?));
+            } else if self.eat('/') {
+                node = Node::Divide(Box::new(node), Box::new(self.unary()?));
+            } else if matches!(
+                self.peek(),
+                Some(Token::Number(_) | Token::Name(_) | Token::Symbol('('))
+            ) {
+                node = Node::Multiply(Box::new(node), Box::new(self.power()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            Ok(Node::Negate(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    // Raising to a power, which goes right to left, so `2^3^2` is `2^9`
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Node::Power(Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let Some(token) = self.peek().cloned() else {
+            return Err("expected a number, variable or function".to_string());
+        };
+        self.position += 1;
+
+        match token {
+            Token::Number(number) => Ok(Node::Number(number)),
+            Token::Symbol('(') => {
+                let node = self.sum()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Token::Symbol(_) => Err(format!("unexpected {token}")),
+            Token::Name(name) => self.name(&name),
+        }
+    }
+
+    // A variable, constant or function call
+    fn name(&mut self, name: &str) -> Result<Node, String> {
+        if let Some(index) = self.variables.iter().position(|&variable| variable == name) {
+            return Ok(Node::Variable(index));
+        }
+        if let Some(&(_, value)) = CONSTANTS.iter().find(|&&(constant, _)| constant == name) {
+            return Ok(Node::Number(value));
+        }
+
+        if let Some(&(_, function)) = FUNCTIONS.iter().find(|&&(other, _)| other == name) {
+            // Without parentheses, the function applies to just the next number or variable
+            let argument = if self.eat('(') {
+                let argument = self.sum()?;
+                self.expect(')')?;
+                argument
+            } else {
+                self.power()?
+            };
+            return Ok(Node::Call(function, Box::new(argument)));
+        }
+
+        if let Some(&(_, function)) = FUNCTIONS_2.iter().find(|&&(other, _)| other == name) {
+            self.expect('(')?;
+            let first = self.sum()?;
+            self.expect(',')?;
+            let second = self.sum()?;
+            self.expect(')')?;
+            return Ok(Node::Call2(function, Box::new(first), Box::new(second)));
+        }
+
+        let variables = match self.variables {
+            [] => String::new(),
+            [variable] => format!(", expected {variable} or a function like sin"),
+            [rest @ .., last] => format!(
+                ", expected {} or {last}, or a function like sin",
+                rest.join(", ")
+            ),
+        };
+        Err(format!("unknown name `{name}`{variables}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parses and evaluates an expression in `u` and `v`
+    fn eval(text: &str, u: f64, v: f64) -> f64 {
+        Expression::parse(text, &["u", "v"])
+            .unwrap_or_else(|error| panic!("couldn't parse `{text}`: {error}"))
+            .evaluate(&[u, v])
+    }
+
+    fn error(text: &str) -> String {
+        Expression::parse(text, &["u", "v"]).unwrap_err()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0, 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0, 0.0), 9.0);
+        assert_eq!(eval("8 - 2 - 1", 0.0, 0.0), 5.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0, 0.0), 1.0);
+        assert_eq!(eval("2 * 3 ^ 2", 0.0, 0.0), 18.0);
+        assert_eq!(eval("2u + v", 3.0, 1.0), 7.0);
+        assert_eq!(eval("u v", 3.0, 2.0), 6.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-u", 2.0, 0.0), -2.0);
+        assert_eq!(eval("--u", 2.0, 0.0), 2.0);
+        assert_eq!(eval("+u", 2.0, 0.0), 2.0);
+        assert_eq!(eval("-2^2", 0.0, 0.0), -4.0);
+        assert_eq!(eval("2^-1", 0.0, 0.0), 0.5);
+        assert_eq!(eval("3 * -u", 2.0, 0.0), -6.0);
+        assert_eq!(eval("1 - -1", 0.0, 0.0), 2.0);
+    }
+
+    #[test]
+    fn power_goes_right_to_left() {
+        assert_eq!(eval("2^3^2", 0.0, 0.0), 512.0);
+        assert_eq!(eval("(2^3)^2", 0.0, 0.0), 64.0);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("sin(0)", 0.0, 0.0), 0.0);
+        assert_eq!(eval("cos u", 0.0, 0.0), 1.0);
+        assert_eq!(eval("sqrt(u + v)", 7.0, 9.0), 4.0);
+        assert_eq!(eval("max(u, v)", 1.0, 2.0), 2.0);
+        assert_eq!(eval("mod(-1, 3)", 0.0, 0.0), 2.0);
+        assert_eq!(eval("hypot(3, 4)", 0.0, 0.0), 5.0);
+        assert_eq!(eval("sign(0)", 0.0, 0.0), 0.0);
+        assert_eq!(eval("2pi", 0.0, 0.0), TAU);
+        assert_eq!(eval("1e-3", 0.0, 0.0), 0.001);
+        assert_eq!(eval("2e", 0.0, 0.0), 2.0 * E);
+
+        // Without parentheses, a function only takes the next number or variable
+        assert_eq!(eval("cos 2u", 1.0, 0.0), 2.0_f64.cos());
+        assert_eq!(eval("sqrt 4 ^ 3", 0.0, 0.0), 8.0);
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        assert_eq!(eval("SIN(PI / 2)", 0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert_eq!(
+            error("u + w"),
+            "unknown name `w`, expected u or v, or a function like sin"
+        );
+        assert_eq!(Expression::parse("t", &[]).unwrap_err(), "unknown name `t`");
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        assert_eq!(error("(u + v"), "expected `)`");
+        assert_eq!(error("u + v)"), "unexpected `)`");
+        assert_eq!(error("max(u v)"), "expected `,`, found `)`");
+        assert_eq!(error("()"), "unexpected `)`");
+    }
+
+    #[test]
+    fn trailing_input() {
+        assert_eq!(error("u +"), "expected a number, variable or function");
+        assert_eq!(error("u, v"), "unexpected `,`");
+        assert_eq!(error("u $ v"), "unexpected `$`");
+        assert_eq!(error("1.2.3"), "invalid number `1.2.3`");
+    }
+}
@@ -0,0 +1,609 @@
+// float.rs
+//
+// A floating-point reference renderer, drawing the same donut as the fixed-point one
+// but with real sines and cosines.
+
+use std::{
+    f64::consts::FRAC_PI_2,
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::{
+    cloud::PointCloud,
+    color::Rgb,
+    frame::{Frame, Pixel},
+    implicit::Implicit,
+    light::{Light, Material},
+    mesh::Mesh,
+    quaternion::Quaternion,
+    renderer::{density, Grid, Render, DEFAULT_SPEED_A, DEFAULT_SPEED_B},
+    scene::{Model, Object, Placement, Scene},
+    sdf::Solid,
+    shapes::Torus,
+    surface::{Sample, Surface},
+};
+
+/// The floating-point types a [`FloatRenderer`] can do its math in: `f32` and `f64`.
+pub trait Real:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Converts from an `f64`, rounding if need be.
+    fn from_f64(value: f64) -> Self;
+    /// Converts to an `f64`.
+    fn to_f64(self) -> f64;
+    /// The sine and cosine of `self`, in radians.
+    fn sin_cos(self) -> (Self, Self);
+    /// The square root of `self`.
+    fn sqrt(self) -> Self;
+    /// Rounds towards zero.
+    fn trunc(self) -> Self;
+}
+
+macro_rules! impl_real {
+    ($type:ty) => {
+        impl Real for $type {
+            fn from_f64(value: f64) -> Self {
+                value as $type
+            }
+
+            fn to_f64(self) -> f64 {
+                f64::from(self)
+            }
+
+            fn sin_cos(self) -> (Self, Self) {
+                <$type>::sin_cos(self)
+            }
+
+            fn sqrt(self) -> Self {
+                <$type>::sqrt(self)
+            }
+
+            fn trunc(self) -> Self {
+                <$type>::trunc(self)
+            }
+        }
+    };
+}
+
+impl_real!(f32);
+impl_real!(f64);
+
+/// How far the viewer is from the center of rotation, K2 in the original code.
+pub const DEFAULT_DISTANCE: f64 = 5.0;
+
+/// Renders the same donut as [`Renderer`](crate::Renderer), but in floating-point math
+/// with real sines and cosines, as a reference to compare the fixed-point version against
+/// or for wherever precision matters more than speed. It can also draw any other
+/// [`Surface`], [`Mesh`] or [`PointCloud`], or raymarch a [`Solid`] or [`Implicit`] surface,
+/// in place of the donut, or a whole [`Scene`] of them at once.
+///
+/// `T` picks the precision of the rotation and projection, either `f32` or `f64`.
+/// Surfaces themselves are always sampled in `f64`.
+#[derive(Clone, Debug)]
+pub struct FloatRenderer<T: Real> {
+    angle_a: T,
+    angle_b: T,
+    speed_a: T,
+    speed_b: T,
+    grid: Grid,
+    distance: f64,
+    zoom: f64,
+    lights: Vec<Light>,
+    // How fast the whole picture turns, on top of angles A and B
+    angular_velocity: [f64; 3],
+    scene: Scene,
+    // How many frames the renderer has advanced by, which is how far each object has
+    // turned and swung around
+    time: f64,
+}
+
+impl<T: Real> FloatRenderer<T> {
+    /// Creates a renderer at the same starting orientation as `donut.c`.
+    pub fn new() -> Self {
+        FloatRenderer {
+            angle_a: T::from_f64(FRAC_PI_2),
+            angle_b: T::from_f64(FRAC_PI_2),
+            speed_a: T::from_f64(DEFAULT_SPEED_A),
+            speed_b: T::from_f64(DEFAULT_SPEED_B),
+            grid: Grid::default(),
+            distance: DEFAULT_DISTANCE,
+            zoom: 1.0,
+            lights: vec![Light::default()],
+            angular_velocity: [0.0; 3],
+            scene: Scene::new().with_object(Object::surface(Torus::default())),
+            time: 0.0,
+        }
+    }
+
+    /// Sets the shape to draw in place of the donut.
+    pub fn with_surface(self, surface: impl Surface + Send + Sync + 'static) -> Self {
+        self.with_scene(Scene::new().with_object(Object::surface(surface)))
+    }
+
+    /// Sets a triangle mesh to draw in place of the donut.
+    pub fn with_mesh(self, mesh: Mesh) -> Self {
+        self.with_scene(Scene::new().with_object(Object::mesh(mesh)))
+    }
+
+    /// Sets a point cloud to draw in place of the donut.
+    pub fn with_point_cloud(self, cloud: PointCloud) -> Self {
+        self.with_scene(Scene::new().with_object(Object::point_cloud(cloud)))
+    }
+
+    /// Sets a solid to raymarch in place of the donut, casting a ray through every pixel
+    /// rather than sampling points on a surface.
+    pub fn with_solid(self, solid: Solid) -> Self {
+        self.with_scene(Scene::new().with_object(Object::solid(solid)))
+    }
+
+    /// Sets an implicit surface to raymarch in place of the donut.
+    pub fn with_implicit(self, implicit: Implicit) -> Self {
+        self.with_scene(Scene::new().with_object(Object::implicit(implicit)))
+    }
+
+    /// Sets a whole scene of objects to draw in place of the donut.
+    pub fn with_scene(mut self, scene: Scene) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    /// Sets the size of the frames to render, in pixels. The donut is scaled and
+    /// centred to fit whatever size is given.
+    pub fn with_size(mut self, width: usize, height: usize) -> Self {
+        self.resize(width, height);
+        self
+    }
+
+    /// Sets how many times taller than it is wide each pixel of the frame will be shown.
+    pub fn with_aspect(mut self, aspect: f64) -> Self {
+        self.grid.aspect = aspect;
+        self
+    }
+
+    /// Sets how far angles A and B advance with each call to [`advance`](Render::advance),
+    /// in radians per frame.
+    pub fn with_speed(mut self, speed_a: f64, speed_b: f64) -> Self {
+        self.speed_a = T::from_f64(speed_a);
+        self.speed_b = T::from_f64(speed_b);
+        self
+    }
+
+    /// Sets how far the viewer is from the center of the scene. Farther away, things look
+    /// smaller and less stretched by perspective.
+    pub fn with_distance(mut self, distance: f64) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Sets how many times bigger to draw everything than the size that fits the donut
+    /// into the frame.
+    pub fn with_zoom(mut self, zoom: f64) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Sets the lights to shade everything by, in place of the one light of the original.
+    pub fn with_lights(mut self, lights: Vec<Light>) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// Sets the whole picture turning at an angular velocity, on top of however angles A
+    /// and B turn it: about the axis `velocity` points along, in view space like a
+    /// [`Light`]'s direction, at as many radians per frame as it's long. Setting both
+    /// speeds to zero with [`with_speed`](FloatRenderer::with_speed) leaves this as the
+    /// only way it turns.
+    pub fn with_angular_velocity(mut self, velocity: [f64; 3]) -> Self {
+        self.angular_velocity = velocity;
+        self
+    }
+
+    /// The current angles A and B, in radians.
+    pub fn angles(&self) -> (f64, f64) {
+        (self.angle_a.to_f64(), self.angle_b.to_f64())
+    }
+
+    fn draw(&self, angle_a: T, angle_b: T) -> Frame {
+        let mut frame = Frame::new(self.grid.width, self.grid.height);
+
+        let mut view = Camera::new(self.grid, angle_a, angle_b)
+            .with_lens(self.distance, self.zoom)
+            .with_lights(&self.lights);
+        if self.angular_velocity != [0.0; 3] {
+            let turned = self.angular_velocity.map(|n| n * self.time);
+            view = view.with_turn(Quaternion::from_rotation_vector(turned));
+        }
+
+        // Every object goes into the same frame, so they hide each other by depth
+        for (object, placement) in self.scene.placements(self.time) {
+            let camera = view
+                .clone()
+                .with_placement(placement)
+                .with_material(object.material);
+            match &object.model {
+                Model::Surface(surface) => self.draw_surface(&**surface, &camera, &mut frame),
+                Model::Mesh(mesh) => mesh.rasterize(&camera, &mut frame),
+                Model::Cloud(cloud) => cloud.splat(&camera, &mut frame),
+                Model::Solid(solid) => solid.march(&camera, &mut frame),
+                Model::Implicit(implicit) => implicit.march(&camera, &mut frame),
+            }
+        }
+
+        frame
+    }
+
+    fn draw_surface(&self, surface: &dyn Surface, camera: &Camera<T>, frame: &mut Frame) {
+        // Sample the surface as densely as the fixed-point renderer samples the donut, taking
+        // more steps as the frame, or the object in it, gets bigger
+        let magnification = self.zoom * DEFAULT_DISTANCE / self.distance;
+        let density = density(self.grid.scale() * magnification * camera.size().to_f64());
+        let [u_range, v_range] = surface.domain();
+        let [u_steps, v_steps] = surface.steps().map(|steps| steps << density);
+        let u_step = (u_range.end - u_range.start) / f64::from(u_steps);
+        let v_step = (v_range.end - v_range.start) / f64::from(v_steps);
+        let two_sided = surface.two_sided();
+
+        for j in 0..u_steps {
+            let u = u_range.start + u_step * f64::from(j);
+
+            for i in 0..v_steps {
+                let v = v_range.start + v_step * f64::from(i);
+                let Sample { point, normal } = surface.sample(u, v);
+
+                let point = camera.view(point);
+                let mut normal = camera.rotate(normal.map(T::from_f64));
+
+                // Light whichever side of a two-sided surface faces the viewer, rather than
+                // leaving it dark wherever the normal happens to point the other way
+                if two_sided && camera.faces_away(point, normal) {
+                    normal = normal.map(|n| -n);
+                }
+
+                if let Some((x, y)) = camera.pixel(point) {
+                    camera.plot(
+                        frame,
+                        x,
+                        y,
+                        camera.luminance(normal).to_f64(),
+                        point[2].to_f64(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Everything needed to turn a point on a shape into a pixel of the frame, at one pair of
+// angles. Points go through it in the same order and direction as in the fixed-point
+// renderer: rotated about the x-axis by A, then about the z-axis by B, then pushed away
+// from the viewer and projected. Before any of that, they're put in place within the
+// scene, as whichever object they belong to.
+#[derive(Clone)]
+pub(crate) struct Camera<T> {
+    rotation: [[T; 3]; 3],
+    size: T,
+    position: [T; 3],
+    // How the whole picture is turned after angles A and B, if it is at all
+    turn: Option<[[T; 3]; 3]>,
+    // Each light's direction, how long that is, and how bright the light is
+    lights: Vec<([T; 3], T, T)>,
+    color: Option<Rgb>,
+    ambient: T,
+    diffuse: T,
+    sin_a: T,
+    cos_a: T,
+    sin_b: T,
+    cos_b: T,
+    center_x: T,
+    center_y: T,
+    scale: T,
+    aspect: T,
+    distance: T,
+}
+
+impl<T: Real> Camera<T> {
+    fn new(grid: Grid, angle_a: T, angle_b: T) -> Self {
+        let (sin_a, cos_a) = angle_a.sin_cos();
+        let (sin_b, cos_b) = angle_b.sin_cos();
+        let (zero, one) = (T::from_f64(0.0), T::from_f64(1.0));
+        Camera {
+            rotation: [[one, zero, zero], [zero, one, zero], [zero, zero, one]],
+            size: one,
+            position: [zero; 3],
+            turn: None,
+            lights: Vec::new(),
+            color: None,
+            ambient: zero,
+            diffuse: one,
+            sin_a,
+            cos_a,
+            sin_b,
+            cos_b,
+            center_x: T::from_f64((grid.width / 2) as f64),
+            center_y: T::from_f64((grid.height / 2) as f64),
+            scale: T::from_f64(grid.scale()),
+            aspect: T::from_f64(grid.aspect),
+            distance: T::from_f64(DEFAULT_DISTANCE),
+        }
+    }
+
+    // Moves the viewer back by `distance`, and magnifies the picture by `zoom`
+    fn with_lens(mut self, distance: f64, zoom: f64) -> Self {
+        self.distance = T::from_f64(distance);
+        self.scale = self.scale * T::from_f64(zoom);
+        self
+    }
+
+    // Turns the whole picture by `rotation`, in view space
+    fn with_turn(mut self, rotation: Quaternion) -> Self {
+        self.turn = Some(rotation.to_matrix().map(|row| row.map(T::from_f64)));
+        self
+    }
+
+    fn with_lights(mut self, lights: &[Light]) -> Self {
+        self.lights = lights
+            .iter()
+            .map(|light| {
+                let length = light.direction.iter().map(|n| n * n).sum::<f64>().sqrt();
+                (
+                    light.direction.map(T::from_f64),
+                    T::from_f64(length),
+                    T::from_f64(light.intensity),
+                )
+            })
+            .collect();
+        self
+    }
+
+    // Shades the object being drawn the way its material says
+    fn with_material(mut self, material: Material) -> Self {
+        self.color = material.color;
+        self.ambient = T::from_f64(material.ambient);
+        self.diffuse = T::from_f64(material.diffuse);
+        self
+    }
+
+    // Puts the object being drawn where it belongs in the scene
+    fn with_placement(mut self, placement: Placement) -> Self {
+        self.rotation = placement
+            .rotation
+            .to_matrix()
+            .map(|row| row.map(T::from_f64));
+        self.size = T::from_f64(placement.scale);
+        self.position = placement.position.map(T::from_f64);
+        self
+    }
+
+    /// How many times bigger the object being drawn is than it was made.
+    pub(crate) fn size(&self) -> T {
+        self.size
+    }
+
+    // Turns a point or normal the way the object being drawn is turned
+    fn turn(&self, vector: [T; 3]) -> [T; 3] {
+        self.rotation
+            .map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+    }
+
+    // Undoes `turn`
+    fn unturn(&self, vector: [T; 3]) -> [T; 3] {
+        let column = |i: usize| {
+            self.rotation[0][i] * vector[0]
+                + self.rotation[1][i] * vector[1]
+                + self.rotation[2][i] * vector[2]
+        };
+        [column(0), column(1), column(2)]
+    }
+
+    /// Rotates a normal of the object being drawn into view space, by the object's own
+    /// rotation and then angles A and B.
+    pub(crate) fn rotate(&self, normal: [T; 3]) -> [T; 3] {
+        self.tumble(self.turn(normal))
+    }
+
+    // Rotates a point or normal by angles A and B, and then however the whole picture is
+    // turned
+    fn tumble(&self, [x, y, z]: [T; 3]) -> [T; 3] {
+        let depth = self.cos_a * y + self.sin_a * z;
+        let up = self.cos_a * z - self.sin_a * y;
+        let tumbled = [
+            self.cos_b * x - self.sin_b * up,
+            self.cos_b * up + self.sin_b * x,
+            depth,
+        ];
+        match self.turn {
+            Some(turn) => {
+                turn.map(|row| row[0] * tumbled[0] + row[1] * tumbled[1] + row[2] * tumbled[2])
+            }
+            None => tumbled,
+        }
+    }
+
+    // Undoes `tumble`
+    fn untumble(&self, vector: [T; 3]) -> [T; 3] {
+        let [x, y, depth] = match self.turn {
+            Some(turn) => {
+                let column = |i: usize| {
+                    turn[0][i] * vector[0] + turn[1][i] * vector[1] + turn[2][i] * vector[2]
+                };
+                [column(0), column(1), column(2)]
+            }
+            None => vector,
+        };
+        let up = self.cos_b * y - self.sin_b * x;
+        [
+            self.cos_b * x + self.sin_b * y,
+            self.cos_a * depth - self.sin_a * up,
+            self.sin_a * depth + self.cos_a * up,
+        ]
+    }
+
+    /// Puts a point of the object being drawn in place, rotates it and moves it out in
+    /// front of the viewer, who sits at the origin looking down the z-axis. The z
+    /// coordinate is then the point's depth.
+    pub(crate) fn view(&self, point: [f64; 3]) -> [T; 3] {
+        let turned = self.turn(point.map(T::from_f64));
+        let placed = [0, 1, 2].map(|axis| turned[axis] * self.size + self.position[axis]);
+        let [x, y, z] = self.tumble(placed);
+        [x, y, z + self.distance]
+    }
+
+    /// Where a point in view space lands on the screen, in pixels, with the pixel at
+    /// column `x` and row `y` covering everything from (x, y) up to (x + 1, y + 1).
+    pub(crate) fn project(&self, [x, y, z]: [T; 3]) -> [T; 2] {
+        [
+            self.center_x + self.scale * x / z,
+            self.center_y + self.scale * y / (self.aspect * z),
+        ]
+    }
+
+    /// The pixel a point in view space lands on, if it's not behind the viewer or off the
+    /// top or left of the screen. Rather than rounding down, this rounds towards the center
+    /// of the screen, like integer division does in the fixed-point renderer.
+    pub(crate) fn pixel(&self, [x, y, z]: [T; 3]) -> Option<(usize, usize)> {
+        // Anything behind the viewer would come out mirrored through the divide
+        if z.to_f64() <= 0.0 {
+            return None;
+        }
+        let screen_x = self.center_x + (self.scale * x / z).trunc();
+        let screen_y = self.center_y + (self.scale * y / (self.aspect * z)).trunc();
+
+        let (screen_x, screen_y) = (screen_x.to_f64(), screen_y.to_f64());
+        (screen_x >= 0.0 && screen_y >= 0.0).then_some((screen_x as usize, screen_y as usize))
+    }
+
+    /// The ray from the viewer through the pixel at column `x` and row `y`, as where it
+    /// starts and which way it goes in the object's own space, before it was put in place
+    /// and rotated. The direction isn't normalized.
+    ///
+    /// The ray goes through the middle of the area that [`pixel`](Camera::pixel) maps onto
+    /// the pixel, which for the row and column through the center of the screen is twice
+    /// as big as any other.
+    pub(crate) fn ray(&self, x: usize, y: usize) -> ([T; 3], [T; 3]) {
+        let offset = |index: usize, center: T| {
+            let offset = index as f64 - center.to_f64();
+            if offset > 0.0 {
+                offset + 0.5
+            } else if offset < 0.0 {
+                offset - 0.5
+            } else {
+                0.0
+            }
+        };
+        let direction = [
+            T::from_f64(offset(x, self.center_x)) / self.scale,
+            T::from_f64(offset(y, self.center_y)) * self.aspect / self.scale,
+            T::from_f64(1.0),
+        ];
+        let origin = self.untumble([T::from_f64(0.0), T::from_f64(0.0), -self.distance]);
+        let origin = self.unturn([0, 1, 2].map(|axis| origin[axis] - self.position[axis]));
+        let origin = origin.map(|n| n / self.size);
+        (origin, self.unturn(self.untumble(direction)))
+    }
+
+    /// Whether a rotated normal at a point in view space points away from the viewer.
+    pub(crate) fn faces_away(&self, [x, y, z]: [T; 3], normal: [T; 3]) -> bool {
+        normal[0] * x + normal[1] * y + normal[2] * z > T::from_f64(0.0)
+    }
+
+    /// How brightly lit a surface with the given rotated normal is, by all the lights
+    /// together and the object's material. By default, like the original, there's one
+    /// light, from above and behind the viewer.
+    pub(crate) fn luminance(&self, normal: [T; 3]) -> T {
+        let zero = T::from_f64(0.0);
+        let lit = self
+            .lights
+            .iter()
+            .fold(zero, |lit, &(direction, length, intensity)| {
+                let facing = (normal[0] * direction[0]
+                    + normal[1] * direction[1]
+                    + normal[2] * direction[2])
+                    / length;
+                // A light behind the surface doesn't take away from the others
+                if facing > zero {
+                    lit + facing * intensity
+                } else {
+                    lit
+                }
+            });
+        self.ambient + self.diffuse * lit
+    }
+
+    /// The color of the object being drawn, if its material gives it one.
+    pub(crate) fn color(&self) -> Option<Rgb> {
+        self.color
+    }
+
+    /// Draws a pixel of the object being drawn into `frame`, in its material's color.
+    pub(crate) fn plot(&self, frame: &mut Frame, x: usize, y: usize, luminance: f64, depth: f64) {
+        frame.plot_pixel(
+            x,
+            y,
+            Pixel {
+                luminance: luminance as f32,
+                depth: depth as f32,
+                color: self.color,
+            },
+        );
+    }
+}
+
+impl<T: Real> Default for FloatRenderer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Real> Render for FloatRenderer<T> {
+    fn render(&self) -> Frame {
+        self.draw(self.angle_a, self.angle_b)
+    }
+
+    fn render_frame(&self, angle_a: f64, angle_b: f64) -> Frame {
+        self.draw(T::from_f64(angle_a), T::from_f64(angle_b))
+    }
+
+    fn advance(&mut self) {
+        self.angle_a = self.angle_a + self.speed_a;
+        self.angle_b = self.angle_b + self.speed_b;
+        self.time += 1.0;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.grid.width = width;
+        self.grid.height = height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Shape;
+
+    // An object behind the viewer shouldn't show up at all, let alone mirrored
+    #[test]
+    fn nothing_behind_the_viewer() {
+        let behind = Object::surface(Shape::Sphere).with_position([0.0, 0.0, -8.0]);
+        let frame = FloatRenderer::<f64>::new()
+            .with_scene(Scene::new().with_object(behind))
+            .with_size(40, 12)
+            .render();
+        assert!(frame.rows().flatten().all(Option::is_none));
+    }
+
+    // Renderers can be handed to another thread to draw on, whatever scene they're drawing
+    #[test]
+    fn renderer_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let scene = Scene::new().with_object(Object::surface(Shape::Cube));
+        assert_send(&FloatRenderer::<f64>::new().with_scene(scene));
+    }
+}
@@ -0,0 +1,140 @@
+// frame.rs
+//
+// A single rendered frame of the donut, decoupled from how it gets printed.
+
+use std::fmt;
+
+use crate::{color::Rgb, ramp::Ramp};
+
+/// The width of the classic `donut.c` text grid, in characters.
+pub const WIDTH: usize = 80;
+/// The height of the classic `donut.c` text grid, in characters.
+pub const HEIGHT: usize = 22;
+
+/// A point on the surface that ended up visible in a frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pixel {
+    /// How directly the surface faces the light, from 0.0 (facing away) to 1.0 (facing it).
+    pub luminance: f32,
+    /// The distance from the viewer to the surface.
+    pub depth: f32,
+    /// The surface's own color, if it has one, to show instead of picking one by luminance.
+    pub color: Option<Rgb>,
+}
+
+/// One frame of output: the luminance and depth of every cell the donut covers.
+#[derive(Clone)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    // Cells that nothing was drawn to are `None`, which doubles as the z-buffer being
+    // set as far away as possible
+    pixels: Vec<Option<Pixel>>,
+}
+
+impl Frame {
+    /// Creates an empty `width` by `height` frame.
+    pub fn new(width: usize, height: usize) -> Self {
+        Frame {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    /// The width of the frame in characters.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the frame in characters.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws a point at column `x` and row `y`, as long as it's within the frame and nothing
+    /// closer has already been drawn there. Returns whether the point was drawn.
+    pub fn plot(&mut self, x: usize, y: usize, luminance: f32, depth: f32) -> bool {
+        self.plot_pixel(
+            x,
+            y,
+            Pixel {
+                luminance,
+                depth,
+                color: None,
+            },
+        )
+    }
+
+    /// Like [`plot`](Frame::plot), but draws a whole pixel, color and all.
+    pub fn plot_pixel(&mut self, x: usize, y: usize, pixel: Pixel) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let cell = &mut self.pixels[x + y * self.width];
+        if cell.is_some_and(|existing| existing.depth <= pixel.depth) {
+            return false;
+        }
+        *cell = Some(Pixel {
+            luminance: pixel.luminance.clamp(0.0, 1.0),
+            ..pixel
+        });
+        true
+    }
+
+    /// Returns what was drawn at column `x` and row `y`, or `None` if nothing was or it's
+    /// out of bounds.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Pixel> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels[x + y * self.width]
+    }
+
+    /// Returns the character printed for column `x` and row `y` with the classic luminance
+    /// ramp, or `None` if it's out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        let ramp = Ramp::default();
+        (x < self.width && y < self.height).then(|| {
+            self.pixel(x, y)
+                .map_or(' ', |pixel| ramp.glyph(pixel.luminance))
+        })
+    }
+
+    /// Iterates over the rows of pixels, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Option<Pixel>]> {
+        // `chunks()` panics on a zero chunk size, which an empty frame would otherwise ask for
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    /// The nearest and farthest depths drawn in the frame, or `None` if it's empty.
+    pub fn depth_range(&self) -> Option<(f32, f32)> {
+        self.pixels.iter().flatten().fold(None, |range, pixel| {
+            let (near, far) = range.unwrap_or((pixel.depth, pixel.depth));
+            Some((near.min(pixel.depth), far.max(pixel.depth)))
+        })
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new(WIDTH, HEIGHT)
+    }
+}
+
+// Prints the frame as plain text with the classic luminance ramp, adding a newline
+// after every row
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ramp = Ramp::default();
+        for row in self.rows() {
+            for pixel in row {
+                let character = pixel.map_or(' ', |pixel| ramp.glyph(pixel.luminance));
+                write!(f, "{character}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
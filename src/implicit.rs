@@ -0,0 +1,215 @@
+// implicit.rs
+//
+// Surfaces given as an equation in x, y and z, like `x^2 + y^2 + z^2 = 4`, and a
+// raymarcher that finds where each ray crosses them.
+
+use std::str::FromStr;
+
+use crate::{
+    expr::Expression,
+    float::{Camera, Real},
+    frame::Frame,
+    mesh::{normalize, FIT_RADIUS},
+};
+
+// How many steps a ray takes across the bounds, looking for somewhere the equation
+// changes sides
+const MARCH_STEPS: u32 = 128;
+// How many times to halve the step a crossing was found in, to find exactly where it is
+const BISECTIONS: u32 = 24;
+// The step used to work out normals from how the equation changes, as a fraction of the
+// bounds
+const NORMAL_STEP: f64 = 1e-5;
+
+/// A surface made of every point where an equation in x, y and z holds, like
+/// `x^2 + y^2 + z^2 = 4` for a sphere or `sin x cos y + sin y cos z + sin z cos x = 0`
+/// for a gyroid.
+///
+/// Only the part of the surface within [`bounds`](Implicit::with_bounds) of the origin is
+/// drawn, scaled so that sphere spins in the same space as the donut. Both sides of the
+/// surface are lit, since it doesn't necessarily have an inside and an outside.
+#[derive(Clone, Debug)]
+pub struct Implicit {
+    function: Expression,
+    bounds: f64,
+}
+
+impl Implicit {
+    /// Creates a surface from an expression in `x`, `y` and `z`, parsed with the
+    /// variables in that order, which is zero everywhere on the surface. It's drawn out to
+    /// 3 from the origin, the same size as the donut, unless
+    /// [`with_bounds`](Implicit::with_bounds) says otherwise.
+    pub fn new(function: Expression) -> Self {
+        Implicit {
+            function,
+            bounds: FIT_RADIUS,
+        }
+    }
+
+    /// Sets how far from the origin to draw the surface out to.
+    pub fn with_bounds(mut self, bounds: f64) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    // The value of the function at a point in the donut's space
+    fn value(&self, point: [f64; 3]) -> f64 {
+        let scale = self.bounds / FIT_RADIUS;
+        self.function.evaluate(&point.map(|n| n * scale))
+    }
+
+    // The direction the surface faces at `point`, from how the function changes around it
+    fn normal(&self, point: [f64; 3]) -> [f64; 3] {
+        let step = NORMAL_STEP * FIT_RADIUS;
+        normalize([0, 1, 2].map(|axis| {
+            let (mut ahead, mut behind) = (point, point);
+            ahead[axis] += step;
+            behind[axis] -= step;
+            self.value(ahead) - self.value(behind)
+        }))
+    }
+
+    /// Casts a ray through every pixel of `frame`, and draws wherever it first crosses the
+    /// surface.
+    pub(crate) fn march<T: Real>(&self, camera: &Camera<T>, frame: &mut Frame) {
+        for y in 0..frame.height() {
+            for x in 0..frame.width() {
+                let (origin, direction) = camera.ray(x, y);
+                let origin = origin.map(T::to_f64);
+                let direction = normalize(direction.map(T::to_f64));
+                let along = |t: f64| [0, 1, 2].map(|axis| origin[axis] + direction[axis] * t);
+
+                let Some((near, far)) = enter_bounds(origin, direction, FIT_RADIUS) else {
+                    continue;
+                };
+                let Some(t) = self.crossing(along, near, far) else {
+                    continue;
+                };
+
+                // Light whichever side of the surface faces the viewer
+                let point = along(t);
+                let mut normal = self.normal(point);
+                if (0..3)
+                    .map(|axis| normal[axis] * direction[axis])
+                    .sum::<f64>()
+                    > 0.0
+                {
+                    normal = normal.map(|n| -n);
+                }
+
+                let normal = camera.rotate(normal.map(T::from_f64));
+                let depth = camera.view(point)[2];
+                camera.plot(
+                    frame,
+                    x,
+                    y,
+                    camera.luminance(normal).to_f64(),
+                    depth.to_f64(),
+                );
+            }
+        }
+    }
+
+    // How far along a ray it first crosses the surface, between `near` and `far`, if it
+    // does at all
+    fn crossing(&self, along: impl Fn(f64) -> [f64; 3], near: f64, far: f64) -> Option<f64> {
+        let step = (far - near) / f64::from(MARCH_STEPS);
+        let mut before = (near, self.value(along(near)));
+
+        for i in 1..=MARCH_STEPS {
+            let t = near + step * f64::from(i);
+            let after = (t, self.value(along(t)));
+
+            // Skip past anywhere the function can't be worked out, rather than taking it
+            // for a crossing
+            if before.1.is_finite() && after.1.is_finite() && before.1 * after.1 <= 0.0 {
+                return Some(self.bisect(&along, before, after));
+            }
+            before = after;
+        }
+
+        None
+    }
+
+    // Narrows down where between `low` and `high` the function changes sides
+    fn bisect(
+        &self,
+        along: impl Fn(f64) -> [f64; 3],
+        mut low: (f64, f64),
+        mut high: (f64, f64),
+    ) -> f64 {
+        for _ in 0..BISECTIONS {
+            let t = (low.0 + high.0) / 2.0;
+            let middle = (t, self.value(along(t)));
+            if low.1 * middle.1 <= 0.0 {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        (low.0 + high.0) / 2.0
+    }
+}
+
+impl FromStr for Implicit {
+    type Err = String;
+
+    /// Parses an equation in x, y and z, like `x^2 + y^2 + z^2 = 4`, with each side an
+    /// [`Expression`]. Without an `=`, the expression is taken to equal zero. It can be
+    /// followed by how far out to draw it, like `; bounds = 2pi`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut equation = None;
+        let mut bounds = FIT_RADIUS;
+
+        for statement in s.split([';', '\n']).map(str::trim) {
+            if statement.is_empty() {
+                continue;
+            }
+
+            match statement.split_once('=') {
+                Some((name, value)) if name.trim() == "bounds" => {
+                    bounds = Expression::parse(value, &[])
+                        .map_err(|error| format!("in the bounds: {error}"))?
+                        .evaluate(&[]);
+                    if !(bounds.is_finite() && bounds > 0.0) {
+                        return Err(format!("invalid bounds `{}`", value.trim()));
+                    }
+                }
+                _ if equation.is_some() => {
+                    return Err(format!("expected only one equation, found `{statement}`"))
+                }
+                Some((left, right)) => {
+                    let left = Expression::parse(left, &["x", "y", "z"])?;
+                    let right = Expression::parse(right, &["x", "y", "z"])?;
+                    equation = Some(left - right);
+                }
+                None => equation = Some(Expression::parse(statement, &["x", "y", "z"])?),
+            }
+        }
+
+        let equation = equation.ok_or_else(|| "missing an equation".to_string())?;
+        Ok(Implicit::new(equation).with_bounds(bounds))
+    }
+}
+
+// Where a ray goes into and comes out of the sphere of `radius` around the origin that a
+// surface is drawn within, if it goes through it at all and not only behind the viewer
+pub(crate) fn enter_bounds(
+    origin: [f64; 3],
+    direction: [f64; 3],
+    radius: f64,
+) -> Option<(f64, f64)> {
+    let dot = |a: [f64; 3], b: [f64; 3]| (0..3).map(|axis| a[axis] * b[axis]).sum::<f64>();
+
+    // Solve |origin + t direction| = radius for t, with the direction normalized
+    let half_b = dot(origin, direction);
+    let c = dot(origin, origin) - radius * radius;
+    let discriminant = half_b * half_b - c;
+    if discriminant <= 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let (near, far) = (-half_b - root, -half_b + root);
+    (far > 0.0).then_some((near.max(0.0), far))
+}
@@ -0,0 +1,55 @@
+// donut.rs
+//
+// A simple reimplementation of donut.c in Rust, usable as a library.
+
+//! Renders Andy Sloane's spinning ASCII donut.
+//!
+//! ```no_run
+//! use donut::Renderer;
+//!
+//! let mut renderer = Renderer::new();
+//! loop {
+//!     print!("{}", renderer.render());
+//!     renderer.advance();
+//! }
+//! ```
+
+mod cloud;
+mod color;
+mod compare;
+mod expr;
+mod float;
+mod frame;
+mod implicit;
+mod light;
+mod mesh;
+mod parametric;
+pub mod parse;
+mod quaternion;
+mod ramp;
+mod renderer;
+mod scene;
+mod sdf;
+mod shapes;
+mod style;
+mod surface;
+pub mod terminal;
+
+pub use cloud::PointCloud;
+pub use color::{ColorMode, Gradient, Rgb};
+pub use compare::Comparison;
+pub use expr::Expression;
+pub use float::{FloatRenderer, Real, DEFAULT_DISTANCE};
+pub use frame::{Frame, Pixel, HEIGHT, WIDTH};
+pub use implicit::Implicit;
+pub use light::{Light, Material};
+pub use mesh::Mesh;
+pub use parametric::Parametric;
+pub use quaternion::Quaternion;
+pub use ramp::Ramp;
+pub use renderer::{Render, Renderer, DEFAULT_SPEED_A, DEFAULT_SPEED_B, MAX_SPEED};
+pub use scene::{Object, Scene};
+pub use sdf::Solid;
+pub use shapes::{Cone, Cube, Cylinder, Klein, Mobius, Shape, Sphere, Torus};
+pub use style::{Mode, Style};
+pub use surface::{numeric_normal, Sample, Surface};
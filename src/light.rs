@@ -0,0 +1,51 @@
+// light.rs
+//
+// Lights to shade things by, and the materials they shine on.
+
+use std::f64::consts::SQRT_2;
+
+use crate::color::Rgb;
+
+/// A light shining from far away, so it falls on everything from the same direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    /// Which way the light comes from, in view space: x to the right, y down the screen and
+    /// z away from the viewer. It doesn't need to be normalized.
+    pub direction: [f64; 3],
+    /// How bright the light is, where 1.0 lights a surface facing it fully.
+    pub intensity: f64,
+}
+
+impl Default for Light {
+    /// The light of the original, from above and behind the viewer. It's just as bright as
+    /// the original's too, which tops out at sqrt(2) in steps of 1/8, a little short of the
+    /// twelve steps its characters go up to.
+    fn default() -> Self {
+        Light {
+            direction: [0.0, -1.0, -1.0],
+            intensity: SQRT_2 * 8.0 / 12.0,
+        }
+    }
+}
+
+/// How a surface takes the light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// The surface's own color, to show instead of one picked from the gradient.
+    pub color: Option<Rgb>,
+    /// How bright the surface is even where no light falls on it.
+    pub ambient: f64,
+    /// How much of the light falling on the surface it gives back.
+    pub diffuse: f64,
+}
+
+impl Default for Material {
+    /// A plain surface, lit only by the lights, the way the donut always has been.
+    fn default() -> Self {
+        Material {
+            color: None,
+            ambient: 0.0,
+            diffuse: 1.0,
+        }
+    }
+}
@@ -2,112 +2,307 @@
 //
 // A simple reimplementation of donut.c in Rust.
 
-// Because the variable names herein have been updated from the original C names,
-// I've forcibly joined two differing styles. As such, I've opted to suppress
-// the warning that variable names should be in snake case.
-#![allow(non_snake_case)]
-
-use std::{thread::sleep, time::Duration};
-
-// Reimplementing the R(mul,shift,x,y) function from the original code
-fn rotate(multiplier: i32, shift: i32, x: &mut i32, y: &mut i32) {
-    let mut temp: i32 = *x;
-    *x -= multiplier * *y >> shift;
-    *y += multiplier * temp >> shift;
-    temp = 3145728 - *x * *x - *y * *y >> 11;
-    *x = *x * temp >> 10;
-    *y = *y * temp >> 10;
-}
+mod cli;
+mod scene_file;
+
+use std::{
+    fs,
+    path::Path,
+    process,
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
-// Though this constant is, strictly speaking, unnecessary,
-// I've chosen to include it rather than copy the value five times.
-const BUFFER_SIZE: usize = 1760;
+use cli::{Options, RendererKind, USAGE};
+use donut::{
+    terminal, Comparison, FloatRenderer, Mesh, Object, PointCloud, Real, Render, Renderer, Scene,
+    Style, HEIGHT, WIDTH,
+};
 
 fn main() {
-    let mut buffer: [char; BUFFER_SIZE];
-    let mut z_buffer: [i8; BUFFER_SIZE];
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    if options.help {
+        print!("{USAGE}");
+        return;
+    }
 
-    let mut sin_A: i32 = 1024;
-    let mut cos_A: i32 = 0;
-    let mut sin_B: i32 = 1024;
-    let mut cos_B: i32 = 0;
+    if options.compare {
+        compare(options);
+    } else if options.still {
+        still(options);
+    } else {
+        animate(options);
+    }
+}
 
-    loop {
-        // Reset the text buffer and z-buffer at the beginning of each frame
-        buffer = [' '; BUFFER_SIZE];
-        z_buffer = [i8::MAX; BUFFER_SIZE];
-
-        let mut sin_j: i32 = 0;
-        let mut cos_j: i32 = 1024;
-
-        for _j in 0..90 {
-            let mut sin_i: i32 = 0;
-            let mut cos_i: i32 = 1024;
-
-            for _i in 0..324 {
-                // The variables here have their original names appended to the end of
-                // their new names for easy identification in the original source code.
-                let minor_radius_r1: i32 = 1;
-                let major_radius_r2: i32 = 2048;
-                let distance_constant_k2: i32 = 5120 * 1024;
-
-                // I decided to forgo the type annotations for the eight successive
-                // variables, but as you may guess, they are all i32 types.
-                let x0 = minor_radius_r1 * cos_j + major_radius_r2;
-                let x1 = cos_i * x0 >> 10;
-                let x2 = cos_A * sin_j >> 10;
-                let x3 = sin_i * x0 >> 10;
-                let x4 = minor_radius_r1 * x2 - (sin_A * x3 >> 10);
-                let x5 = sin_A * sin_j >> 10;
-                let x6 = distance_constant_k2 + minor_radius_r1 * 1024 * x5 + cos_A * x3;
-                let x7 = cos_j * sin_i >> 10;
-
-                let x: i32 = 40 + 30 * (cos_B * x1 - sin_B * x4) / x6;
-                let y: i32 = 12 + 15 * (cos_B * x4 + sin_A * x1) / x6;
-
-                // Convert the luminance index to a usize type after calculating via shadowing
-                let luminance_index: i32 = (-1 * cos_A * x7
-                    - cos_B * ((-1 * sin_A * x7 >> 10) + x2)
-                    - cos_i * (cos_j * sin_B >> 10)
-                    >> 10)
-                    - x5
-                    >> 7;
-                let luminance_index: usize = usize::try_from(luminance_index).unwrap_or(0);
-
-                // This line of code was generated by A.I. (Anthropic's Claude 3 Sonnet) on April 4, 2024,
-                // as I could not solve the issue myself.
-                let o: usize =
-                    (x as usize) + ((y as isize).wrapping_mul(80) as usize) % BUFFER_SIZE;
-
-                let zz: i8 = i8::try_from((x6 - distance_constant_k2) >> 15)
-                    .expect("Couldn't convert zz to i8!");
-
-                if 22 > y && y > 0 && x > 0 && 80 > x && zz < z_buffer[o] {
-                    z_buffer[o] = zz;
-                    buffer[o] = ['.', ',', '-', '~', ':', ';', '=', '!', '*', '#', '$', '@']
-                        [luminance_index];
-                }
-                rotate(5, 8, &mut cos_i, &mut sin_i);
+// Renders a single frame, without any of the escape codes for animating it
+fn still(options: Options) {
+    let (columns, rows) = options.size.unwrap_or((WIDTH, HEIGHT));
+    let (renderer, style) = setup(&options, columns, rows);
+
+    let frame = match (options.angle_a, options.angle_b) {
+        (None, None) => renderer.render(),
+        // The starting orientation has both angles at a quarter turn
+        (angle_a, angle_b) => renderer.render_frame(
+            angle_a.unwrap_or(std::f64::consts::FRAC_PI_2),
+            angle_b.unwrap_or(std::f64::consts::FRAC_PI_2),
+        ),
+    };
+
+    // Trailing spaces are just noise in a banner or a README
+    let text = style.paint_trimmed(&frame);
+
+    match &options.output {
+        Some(path) => {
+            if let Err(error) = fs::write(path, text) {
+                eprintln!("error: couldn't write to {}: {error}", path.display());
+                process::exit(1);
             }
-            rotate(9, 7, &mut cos_j, &mut sin_j);
         }
+        None => print!("{text}"),
+    }
+}
 
-        // Prints the text buffer to the screen,
-        // adding newlines every 80th character
-        for k in 0..1761 {
-            match k % 80 {
-                0 => print!("\n"),
-                1.. => print!("{}", buffer[k]),
-            }
+// Renders a whole rotation through the fixed-point renderer and a floating-point one,
+// and reports how much they disagree
+fn compare(options: Options) {
+    let reference = options.renderer.unwrap_or(RendererKind::F64);
+    if reference == RendererKind::Fixed {
+        eprintln!("error: the fixed-point renderer can't be compared against itself");
+        process::exit(2);
+    }
+
+    let (columns, rows) = options.size.unwrap_or((WIDTH, HEIGHT));
+    let (width, height) = options.mode.frame_size(columns, rows);
+    let mut fixed = new_renderer(RendererKind::Fixed, &options, width, height);
+    let mut float = new_renderer(reference, &options, width, height);
+
+    // By default, go around until the slower of the two angles has made a full turn
+    let slowest = options.speed_a.abs().min(options.speed_b.abs());
+    let frames = options.frames.unwrap_or_else(|| {
+        if slowest > 0.0 {
+            (std::f64::consts::TAU / slowest).ceil() as u64
+        } else {
+            1
+        }
+    });
+
+    // Both renderers get advanced frame by frame, rather than jumping to each angle, so
+    // any drift in the fixed-point rotation shows up too
+    let mut comparison = Comparison::new(options.ramp.clone());
+    for _ in 0..frames {
+        comparison.add(&fixed.render(), &float.render());
+        fixed.advance();
+        float.advance();
+    }
+
+    println!("{comparison}");
+}
+
+fn animate(options: Options) {
+    // Asked for no frames at all, so there's nothing to draw
+    if options.frames == Some(0) {
+        return;
+    }
+
+    let (mut columns, mut rows) = text_size(&options);
+    let (mut renderer, style) = setup(&options, columns, rows);
+    terminal::watch_resize();
+    terminal::watch_interrupt();
+
+    // Hides the cursor until it goes out of scope, whether that's from finishing normally,
+    // Ctrl-C breaking out of the loop below, or a panic
+    let guard = terminal::Guard::new(options.alternate_screen);
+
+    let start = Instant::now();
+    let mut frames_drawn: u64 = 0;
+    let mut previous_height: Option<usize> = None;
+
+    loop {
+        if terminal::take_resized() && options.size.is_none() {
+            (columns, rows) = text_size(&options);
+            let (width, height) = style.mode.frame_size(columns, rows);
+            renderer.resize(width, height);
+
+            // Whatever was on screen has likely been reflowed by the terminal, so rather than
+            // trying to draw over it, clear the screen and start again from the top
+            print!("\x1b[2J\x1b[H");
+            previous_height = None;
+        }
+
+        // Move the cursor back up to print this frame over the previous one. Terminals take
+        // moving up no rows as moving up one, so a frame with no rows has to skip it.
+        if let Some(height) = previous_height.filter(|&height| height > 0) {
+            print!("\x1b[{height}A");
         }
 
-        rotate(5, 7, &mut cos_A, &mut sin_A);
-        rotate(5, 8, &mut cos_B, &mut sin_B);
+        let frame = renderer.render();
+        print!("{}", style.paint(&frame));
+        previous_height = Some(rows);
+        frames_drawn += 1;
+
+        renderer.advance();
+
+        let out_of_frames = options.frames.is_some_and(|frames| frames_drawn >= frames);
+        let out_of_time = options
+            .duration
+            .is_some_and(|duration| start.elapsed() >= duration);
+        if out_of_frames || out_of_time || terminal::interrupted() {
+            break;
+        }
 
         // Pause between frames
-        sleep(Duration::from_millis(35));
+        pause(options.delay);
+    }
+
+    drop(guard);
+
+    // Exit the way a shell expects from a process stopped by Ctrl-C
+    if terminal::interrupted() {
+        process::exit(130);
+    }
+}
+
+// How long to sleep at a time while pausing between frames, before checking for Ctrl-C
+const PAUSE_SLICE: Duration = Duration::from_millis(15);
+
+// Waits for `delay`, a slice at a time, so that Ctrl-C doesn't have to wait for a long
+// delay to run out before it's noticed
+fn pause(delay: Duration) {
+    let end = Instant::now() + delay;
+    while !terminal::interrupted() {
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        sleep(left.min(PAUSE_SLICE));
+    }
+}
+
+// Builds the renderer and style for output that's `columns` by `rows` characters
+fn setup(options: &Options, columns: usize, rows: usize) -> (Box<dyn Render>, Style) {
+    let (width, height) = options.mode.frame_size(columns, rows);
+    // The fixed-point renderer only knows how to draw the donut
+    let kind = options.renderer.unwrap_or(if options.draws_torus() {
+        RendererKind::Fixed
+    } else {
+        RendererKind::F64
+    });
+    let renderer = new_renderer(kind, options, width, height);
+    let style = Style {
+        mode: options.mode,
+        ramp: options.ramp.clone(),
+        color: options.color,
+        gradient: options.gradient.clone(),
+        depth_shading: options.depth_shading,
+    };
+    (renderer, style)
+}
 
-        // Reset the cursor to print the next frame over the current one
-        print!("\x1b[23A");
+// Builds a renderer of the given kind, for frames that are `width` by `height` pixels
+fn new_renderer(
+    kind: RendererKind,
+    options: &Options,
+    width: usize,
+    height: usize,
+) -> Box<dyn Render> {
+    let aspect = options.mode.aspect();
+    let (speed_a, speed_b) = (options.speed_a, options.speed_b);
+    match kind {
+        RendererKind::Fixed => Box::new(
+            Renderer::new()
+                .with_size(width, height)
+                .with_aspect(aspect)
+                .with_speed(speed_a, speed_b),
+        ),
+        RendererKind::F32 => Box::new(
+            float_renderer::<f32>(options)
+                .with_size(width, height)
+                .with_aspect(aspect)
+                .with_speed(speed_a, speed_b),
+        ),
+        RendererKind::F64 => Box::new(
+            float_renderer::<f64>(options)
+                .with_size(width, height)
+                .with_aspect(aspect)
+                .with_speed(speed_a, speed_b),
+        ),
     }
 }
+
+// Builds a floating-point renderer for whichever shape, model or scene was asked for
+fn float_renderer<T: Real>(options: &Options) -> FloatRenderer<T> {
+    FloatRenderer::new()
+        .with_scene(scene(options))
+        .with_distance(options.distance)
+        .with_zoom(options.zoom)
+        .with_lights(options.lights.clone())
+        .with_angular_velocity(options.spin)
+}
+
+// Puts whatever was asked for into a scene, which is just the one object unless there were
+// several
+fn scene(options: &Options) -> Scene {
+    if !options.objects.is_empty() {
+        return options
+            .objects
+            .iter()
+            .cloned()
+            .fold(Scene::new(), Scene::with_object);
+    }
+
+    let object = if let Some(surface) = &options.surface {
+        Object::surface(surface.clone().fitted())
+    } else if let Some(implicit) = &options.implicit {
+        Object::implicit(implicit.clone())
+    } else if let Some(solid) = &options.solid {
+        Object::solid(solid.clone())
+    } else if let Some(path) = &options.model {
+        load_model(path, options.smooth).unwrap_or_else(|error| {
+            eprintln!("error: couldn't load {}: {error}", path.display());
+            process::exit(1);
+        })
+    } else {
+        let shape = options.shape.unwrap_or_default();
+        match shape.solid() {
+            Some(solid) if options.raymarch => Object::solid(solid),
+            _ => Object::surface(shape),
+        }
+    };
+    Scene::new().with_object(object)
+}
+
+// Loads a mesh or point cloud, going by the file's extension to tell what format it's in
+fn load_model(path: &Path, smooth: bool) -> Result<Object, String> {
+    let source = fs::read(path).map_err(|error| error.to_string())?;
+    let text = || std::str::from_utf8(&source).map_err(|_| "not valid UTF-8".to_string());
+    let prepare = |mesh: Mesh| Object::mesh(if smooth { mesh.smoothed() } else { mesh }.fitted());
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("obj") => Ok(prepare(Mesh::from_obj(text()?)?)),
+        Some("stl") => Ok(prepare(Mesh::from_stl(&source)?)),
+        Some("ply") => Ok(Object::point_cloud(PointCloud::from_ply(&source)?.fitted())),
+        Some("xyz") => Ok(Object::point_cloud(PointCloud::from_xyz(text()?)?.fitted())),
+        _ => Err("unrecognized format, expected a .obj, .stl, .ply or .xyz file".to_string()),
+    }
+}
+
+// Works out how many columns and rows of text a frame should take up in the current terminal
+fn text_size(options: &Options) -> (usize, usize) {
+    if let Some(size) = options.size {
+        return size;
+    }
+
+    // Leave the bottom row of the terminal free for the cursor to sit on between frames
+    let (columns, rows) = terminal::size().unwrap_or((WIDTH, HEIGHT + 1));
+    (columns, rows.saturating_sub(1))
+}
@@ -0,0 +1,637 @@
+// mesh.rs
+//
+// Triangle meshes loaded from files, and a scanline rasterizer to draw them with.
+
+use std::collections::HashMap;
+
+use crate::{
+    float::{Camera, Real},
+    frame::Frame,
+};
+
+// How far the farthest point of a fitted mesh is from the origin, the same as the
+// outside edge of the donut
+pub(crate) const FIT_RADIUS: f64 = 3.0;
+
+/// A mesh of triangles, with a normal at each corner of each triangle.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    vertices: Vec<[f64; 3]>,
+    triangles: Vec<Triangle>,
+}
+
+#[derive(Clone, Debug)]
+struct Triangle {
+    // Indices into the mesh's vertices
+    corners: [usize; 3],
+    normals: [[f64; 3]; 3],
+}
+
+// A corner of a triangle once it's been projected onto the screen
+#[derive(Clone, Copy)]
+struct Corner {
+    x: f64,
+    y: f64,
+    depth: f64,
+    normal: [f64; 3],
+}
+
+impl Mesh {
+    /// Parses a mesh from the text of a Wavefront `.obj` file.
+    ///
+    /// Only the vertices, normals and faces are read, and faces with more than three
+    /// corners are split into triangles. Faces without normals get flat shading.
+    pub fn from_obj(source: &str) -> Result<Mesh, String> {
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut triangles = Vec::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+
+            let result = match words.next() {
+                Some("v") => parse_vector(words).map(|vertex| vertices.push(vertex)),
+                Some("vn") => parse_vector(words).map(|normal| normals.push(normalize(normal))),
+                Some("f") => words
+                    .map(|word| parse_corner(word, vertices.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .and_then(|corners| {
+                        if corners.len() < 3 {
+                            return Err("a face needs at least three corners".to_string());
+                        }
+
+                        // Split the face into a fan of triangles around its first corner
+                        for index in 1..corners.len() - 1 {
+                            let fan = [corners[0], corners[index], corners[index + 1]];
+                            let positions = fan.map(|(vertex, _)| vertices[vertex]);
+                            let normals = match fan.map(|(_, normal)| normal) {
+                                [Some(a), Some(b), Some(c)] => [a, b, c].map(|n| normals[n]),
+                                _ => [face_normal(positions); 3],
+                            };
+                            triangles.push(Triangle {
+                                corners: fan.map(|(vertex, _)| vertex),
+                                normals,
+                            });
+                        }
+                        Ok(())
+                    }),
+                // Texture coordinates, groups, materials and so on don't change the shape
+                _ => Ok(()),
+            };
+            result.map_err(|error| format!("line {}: {error}", number + 1))?;
+        }
+
+        if triangles.is_empty() {
+            return Err("no faces found".to_string());
+        }
+        Ok(Mesh {
+            vertices,
+            triangles,
+        })
+    }
+
+    /// Parses a mesh from an STL file, in either its binary or its ASCII form.
+    ///
+    /// Triangles get flat shading, worked out from their corners rather than the normals
+    /// stored in the file, which are often missing or wrong.
+    pub fn from_stl(source: &[u8]) -> Result<Mesh, String> {
+        // Binary files can start with `solid` too, so go by whether the size adds up
+        let binary_size = source.get(80..84).map(|count| {
+            84 + 50 * u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize
+        });
+        if !source.starts_with(b"solid") || binary_size == Some(source.len()) {
+            return Mesh::from_binary_stl(source);
+        }
+
+        let source = std::str::from_utf8(source).map_err(|_| "not valid binary or ASCII STL")?;
+        let mut triangles = Vec::new();
+        let mut corners = Vec::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let result = match words.next() {
+                Some("vertex") => parse_vector(words).map(|vertex| corners.push(vertex)),
+                Some("outer") => {
+                    corners.clear();
+                    Ok(())
+                }
+                Some("endloop") => match corners[..] {
+                    [a, b, c] => {
+                        triangles.push([a, b, c]);
+                        Ok(())
+                    }
+                    _ => Err("a facet needs exactly three vertices".to_string()),
+                },
+                _ => Ok(()),
+            };
+            result.map_err(|error| format!("line {}: {error}", number + 1))?;
+        }
+
+        Mesh::from_triangles(triangles)
+    }
+
+    fn from_binary_stl(source: &[u8]) -> Result<Mesh, String> {
+        let facets = source
+            .get(84..)
+            .ok_or_else(|| "too short to be an STL file".to_string())?;
+        if facets.len() % 50 != 0 {
+            return Err("not valid binary or ASCII STL".to_string());
+        }
+
+        // Each facet is a normal and three vertices, as twelve little-endian floats, and
+        // then two bytes that nothing uses
+        let triangles = facets
+            .chunks_exact(50)
+            .enumerate()
+            .map(|(number, facet)| {
+                let float = |index: usize| {
+                    let bytes = &facet[index * 4..index * 4 + 4];
+                    f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                };
+                let corners =
+                    [1, 2, 3].map(|corner| [0, 1, 2].map(|axis| float(corner * 3 + axis)));
+                if corners.iter().flatten().all(|n| n.is_finite()) {
+                    Ok(corners)
+                } else {
+                    Err(format!(
+                        "triangle {}: a coordinate isn't a finite number",
+                        number + 1
+                    ))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Mesh::from_triangles(triangles)
+    }
+
+    // Builds a flat-shaded mesh out of separate triangles, joining up the corners they
+    // share so they can be smoothed later
+    fn from_triangles(triangles: Vec<[[f64; 3]; 3]>) -> Result<Mesh, String> {
+        if triangles.is_empty() {
+            return Err("no triangles found".to_string());
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = HashMap::new();
+        let triangles = triangles
+            .into_iter()
+            .map(|positions| Triangle {
+                // Add zero to turn any -0.0 into 0.0, so the two don't count as different
+                corners: positions.map(|position| {
+                    let key = position.map(|n| (n + 0.0).to_bits());
+                    *indices.entry(key).or_insert_with(|| {
+                        vertices.push(position);
+                        vertices.len() - 1
+                    })
+                }),
+                normals: [face_normal(positions); 3],
+            })
+            .collect();
+
+        Ok(Mesh {
+            vertices,
+            triangles,
+        })
+    }
+
+    /// Replaces the mesh's normals so the shading blends smoothly across each vertex,
+    /// by averaging the normals of every triangle that shares it. Bigger triangles count
+    /// for more.
+    pub fn smoothed(mut self) -> Self {
+        let mut sums = vec![[0.0; 3]; self.vertices.len()];
+        for triangle in &self.triangles {
+            let normal = area_normal(triangle.corners.map(|index| self.vertices[index]));
+            for &index in &triangle.corners {
+                sums[index] = [0, 1, 2].map(|axis| sums[index][axis] + normal[axis]);
+            }
+        }
+
+        for triangle in &mut self.triangles {
+            triangle.normals = triangle.corners.map(|index| normalize(sums[index]));
+        }
+        self
+    }
+
+    /// Moves and scales the mesh so it's centered on the origin and spins in the same
+    /// space as the donut, whatever units it was made in.
+    pub fn fitted(mut self) -> Self {
+        fit(&mut self.vertices);
+        self
+    }
+
+    /// The number of triangles in the mesh.
+    pub fn triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Draws every triangle of the mesh into `frame`.
+    pub(crate) fn rasterize<T: Real>(&self, camera: &Camera<T>, frame: &mut Frame) {
+        let views: Vec<[T; 3]> = self.vertices.iter().map(|&v| camera.view(v)).collect();
+
+        for triangle in &self.triangles {
+            let points = triangle.corners.map(|index| views[index]);
+            if points.iter().any(|point| point[2].to_f64() <= 0.0) {
+                continue;
+            }
+
+            // Meshes often get their triangles wound inconsistently, or their normals turned
+            // inside out, so rather than trusting which way the normals point, light
+            // whichever side of each triangle is in view. The normals get turned to that
+            // side, going by the triangle's own flat normal, which unlike smoothed normals
+            // can't point away from the viewer while the triangle itself faces them.
+            let mut facing = camera.rotate(
+                area_normal(triangle.corners.map(|index| self.vertices[index])).map(T::from_f64),
+            );
+            if camera.faces_away(points[0], facing) {
+                facing = facing.map(|n| -n);
+            }
+            let normals = triangle
+                .normals
+                .map(|normal| camera.rotate(normal.map(T::from_f64)));
+            let average =
+                [0, 1, 2].map(|axis| normals[0][axis] + normals[1][axis] + normals[2][axis]);
+            let agreement = (0..3).fold(T::from_f64(0.0), |sum, axis| {
+                sum + average[axis] * facing[axis]
+            });
+            let flip = agreement < T::from_f64(0.0);
+
+            let mut corners = [0, 1, 2].map(|corner| {
+                let [x, y] = camera.project(points[corner]);
+                Corner {
+                    x: x.to_f64(),
+                    y: y.to_f64(),
+                    depth: points[corner][2].to_f64(),
+                    normal: normals[corner].map(|n| if flip { -n.to_f64() } else { n.to_f64() }),
+                }
+            });
+            corners.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+            fill(corners, camera, frame);
+        }
+    }
+}
+
+// Fills in a projected triangle one row at a time, with its corners sorted top to bottom.
+// A pixel gets drawn if its center is inside the triangle.
+fn fill<T: Real>(corners: [Corner; 3], camera: &Camera<T>, frame: &mut Frame) {
+    let [top, middle, bottom] = corners;
+
+    // Twice the signed area of the triangle from a to b to (x, y), for working out how
+    // close a pixel is to each corner
+    let cross =
+        |a: &Corner, b: &Corner, x: f64, y: f64| (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
+    let area = cross(&top, &middle, bottom.x, bottom.y);
+    if area.abs() < f64::EPSILON {
+        return;
+    }
+
+    let first_row = (top.y - 0.5).ceil().max(0.0);
+    let last_row = (bottom.y - 0.5).floor().min(frame.height() as f64 - 1.0);
+    if first_row > last_row {
+        return;
+    }
+
+    for row in first_row as usize..=last_row as usize {
+        let center_y = row as f64 + 0.5;
+
+        // Find where the row crosses the long edge from top to bottom, and whichever of the
+        // two short edges it's level with
+        let crossing = |a: &Corner, b: &Corner| {
+            let t = if b.y > a.y {
+                (center_y - a.y) / (b.y - a.y)
+            } else {
+                0.0
+            };
+            a.x + (b.x - a.x) * t
+        };
+        let long = crossing(&top, &bottom);
+        let short = if center_y < middle.y {
+            crossing(&top, &middle)
+        } else {
+            crossing(&middle, &bottom)
+        };
+
+        let first_column = (long.min(short) - 0.5).ceil().max(0.0);
+        let last_column = (long.max(short) - 0.5)
+            .floor()
+            .min(frame.width() as f64 - 1.0);
+        if first_column > last_column {
+            continue;
+        }
+
+        for column in first_column as usize..=last_column as usize {
+            let center_x = column as f64 + 0.5;
+
+            // Weight each corner by how close the pixel is to it, corrected for perspective
+            // so things don't bend as they get farther away
+            let weights = [
+                cross(&middle, &bottom, center_x, center_y) / area / top.depth,
+                cross(&bottom, &top, center_x, center_y) / area / middle.depth,
+                cross(&top, &middle, center_x, center_y) / area / bottom.depth,
+            ];
+            let depth = 1.0 / weights.iter().sum::<f64>();
+            let normal = [0, 1, 2].map(|axis| {
+                depth
+                    * (weights[0] * top.normal[axis]
+                        + weights[1] * middle.normal[axis]
+                        + weights[2] * bottom.normal[axis])
+            });
+
+            let luminance = camera.luminance(normalize(normal).map(T::from_f64));
+            camera.plot(frame, column, row, luminance.to_f64(), depth);
+        }
+    }
+}
+
+// Reads the three coordinates after a `v` or `vn`. Vertices can have a fourth, which
+// gets ignored.
+fn parse_vector<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<[f64; 3], String> {
+    let mut vector = [0.0; 3];
+    for coordinate in &mut vector {
+        let word = words
+            .next()
+            .ok_or_else(|| "expected three coordinates".to_string())?;
+        // Infinities and NaN would throw off fitting the mesh into view, along with the
+        // shading of every face that touched them
+        *coordinate = word
+            .parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+            .ok_or_else(|| format!("invalid coordinate `{word}`"))?;
+    }
+    Ok(vector)
+}
+
+// Reads one corner of a face, given as `v`, `v/vt`, `v//vn` or `v/vt/vn`, returning the
+// indices of its vertex and normal counting from zero. Indices in the file count from one,
+// or back from the latest vertex if they're negative.
+fn parse_corner(
+    word: &str,
+    vertices: usize,
+    normals: usize,
+) -> Result<(usize, Option<usize>), String> {
+    let resolve = |index: &str, count: usize, kind: &str| {
+        let index: i64 = index
+            .parse()
+            .map_err(|_| format!("invalid {kind} index `{index}`"))?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if (0..count as i64).contains(&resolved) {
+            Ok(resolved as usize)
+        } else {
+            Err(format!("{kind} index {index} is out of range"))
+        }
+    };
+
+    let mut parts = word.split('/');
+    let vertex = resolve(parts.next().unwrap_or_default(), vertices, "vertex")?;
+    let normal = match parts.nth(1) {
+        Some(normal) if !normal.is_empty() => Some(resolve(normal, normals, "normal")?),
+        _ => None,
+    };
+    Ok((vertex, normal))
+}
+
+// The normal of the flat triangle through three points, by the right-hand rule
+fn face_normal(corners: [[f64; 3]; 3]) -> [f64; 3] {
+    normalize(area_normal(corners))
+}
+
+// Like `face_normal()`, but as long as twice the area of the triangle
+fn area_normal([a, b, c]: [[f64; 3]; 3]) -> [f64; 3] {
+    let (u, v) = (
+        [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+        [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+    );
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+// Moves and scales a set of points so they're centered on the origin, with the farthest
+// of them as far out as the edge of the donut
+pub(crate) fn fit(points: &mut [[f64; 3]]) {
+    let (center, scale) = fitting(points);
+    for point in points {
+        *point = [0, 1, 2].map(|axis| (point[axis] - center[axis]) * scale);
+    }
+}
+
+// The center of a set of points, and how much to scale them by around it, to fit them the
+// way `fit` does
+pub(crate) fn fitting(points: &[[f64; 3]]) -> ([f64; 3], f64) {
+    let Some(&first) = points.first() else {
+        return ([0.0; 3], 1.0);
+    };
+    let (low, high) = points.iter().fold((first, first), |(low, high), point| {
+        (
+            [0, 1, 2].map(|axis| low[axis].min(point[axis])),
+            [0, 1, 2].map(|axis| high[axis].max(point[axis])),
+        )
+    });
+    let center = [0, 1, 2].map(|axis| (low[axis] + high[axis]) / 2.0);
+
+    let radius = points
+        .iter()
+        .map(|point| length([0, 1, 2].map(|axis| point[axis] - center[axis])))
+        .fold(0.0, f64::max);
+    let scale = if radius > 0.0 {
+        FIT_RADIUS / radius
+    } else {
+        1.0
+    };
+
+    (center, scale)
+}
+
+pub(crate) fn length(vector: [f64; 3]) -> f64 {
+    vector.iter().map(|n| n * n).sum::<f64>().sqrt()
+}
+
+// Scales a vector to a length of one, leaving it as zero if it has no length to scale
+pub(crate) fn normalize(vector: [f64; 3]) -> [f64; 3] {
+    let length = length(vector);
+    if length > f64::EPSILON {
+        vector.map(|n| n / length)
+    } else {
+        [0.0; 3]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corners(mesh: &Mesh) -> Vec<[usize; 3]> {
+        mesh.triangles
+            .iter()
+            .map(|triangle| triangle.corners)
+            .collect()
+    }
+
+    // A binary STL of `triangles`, with a header that starts like an ASCII one to make
+    // telling them apart harder
+    fn binary_stl(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut bytes = b"solid but really binary".to_vec();
+        bytes.resize(80, b' ');
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            // The stored normal, which gets ignored
+            bytes.extend([0.0f32; 3].iter().flat_map(|n| n.to_le_bytes()));
+            for corner in triangle {
+                bytes.extend(corner.iter().flat_map(|n| n.to_le_bytes()));
+            }
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn obj_faces_split_into_fans() {
+        let mesh = Mesh::from_obj(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 0.5 0 1.0\n\
+             f 1 2 3 4 5 # a pentagon\n",
+        )
+        .unwrap();
+        assert_eq!(corners(&mesh), [[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn obj_negative_indices_count_back_from_the_latest_vertex() {
+        let mesh = Mesh::from_obj(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n\
+             v 0 0 1\nv 1 0 1\nv 0 1 1\nf -3 -2 -1\n",
+        )
+        .unwrap();
+        assert_eq!(corners(&mesh), [[0, 1, 2], [3, 4, 5]]);
+    }
+
+    #[test]
+    fn obj_normals_come_from_the_file_or_the_face() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 -3\n";
+
+        // `v/vt/vn` and `v//vn` both give the normal, and it gets normalized
+        let mesh = Mesh::from_obj(&format!("{source}f 1/1/1 2//1 3/1/1\n")).unwrap();
+        assert_eq!(mesh.triangles[0].normals, [[0.0, 0.0, -1.0]; 3]);
+
+        // Without a normal at every corner, the face is shaded flat
+        let mesh = Mesh::from_obj(&format!("{source}f 1/1 2/1/1 3\n")).unwrap();
+        assert_eq!(mesh.triangles[0].normals, [[0.0, 0.0, 1.0]; 3]);
+    }
+
+    #[test]
+    fn obj_errors() {
+        let error = |source: &str| Mesh::from_obj(source).unwrap_err();
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+            "line 3: a face needs at least three corners"
+        );
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"),
+            "line 4: vertex index 4 is out of range"
+        );
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n"),
+            "line 4: vertex index 0 is out of range"
+        );
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1//1 2//1 3//1\n"),
+            "line 4: normal index 1 is out of range"
+        );
+        assert_eq!(error("v 0 0\n"), "line 1: expected three coordinates");
+        assert_eq!(error("v 0 0 x\n"), "line 1: invalid coordinate `x`");
+        assert_eq!(error("v 0 nan 0\n"), "line 1: invalid coordinate `nan`");
+        assert_eq!(
+            error("v 0 0 0\nvn inf 0 1\n"),
+            "line 2: invalid coordinate `inf`"
+        );
+        assert_eq!(error("v 0 0 0\n"), "no faces found");
+    }
+
+    #[test]
+    fn stl_ascii() {
+        let mesh = Mesh::from_stl(
+            b"solid square\n\
+              facet normal 0 0 1\n outer loop\n  vertex 0 0 0\n  vertex 1 0 0\n  vertex 1 1 0\n endloop\nendfacet\n\
+              facet normal 0 0 1\n outer loop\n  vertex 0 0 0\n  vertex 1 1 0\n  vertex 0 1 0\n endloop\nendfacet\n\
+              endsolid square\n",
+        )
+        .unwrap();
+        assert_eq!(corners(&mesh), [[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.triangles[0].normals, [[0.0, 0.0, 1.0]; 3]);
+    }
+
+    #[test]
+    fn stl_ascii_errors() {
+        assert_eq!(
+            Mesh::from_stl(b"solid t\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\n")
+                .unwrap_err(),
+            "line 5: a facet needs exactly three vertices"
+        );
+        assert_eq!(
+            Mesh::from_stl(b"solid empty\nendsolid empty\n").unwrap_err(),
+            "no triangles found"
+        );
+        assert_eq!(
+            Mesh::from_stl(
+                b"solid t\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 1 -infinity 0\nendloop\n"
+            )
+            .unwrap_err(),
+            "line 5: invalid coordinate `-infinity`"
+        );
+    }
+
+    #[test]
+    fn stl_binary_even_when_it_starts_with_solid() {
+        let stl = binary_stl(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+            [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [-0.0, 1.0, 0.0]],
+        ]);
+        let mesh = Mesh::from_stl(&stl).unwrap();
+        // The corners shared between the triangles get joined up, -0.0 and all
+        assert_eq!(corners(&mesh), [[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.vertices.len(), 4);
+    }
+
+    #[test]
+    fn stl_binary_truncated() {
+        let stl = binary_stl(&[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]; 2]);
+        for length in [0, 40, 83, 84 + 50 + 20, stl.len() - 1] {
+            assert!(Mesh::from_stl(&stl[..length]).is_err(), "{length} bytes");
+        }
+
+        // Without the `solid` at the start, it can only be binary
+        let mut stl = stl;
+        stl[..5].copy_from_slice(b"model");
+        assert_eq!(
+            Mesh::from_stl(&stl[..40]).unwrap_err(),
+            "too short to be an STL file"
+        );
+        assert_eq!(
+            Mesh::from_stl(&stl[..stl.len() - 1]).unwrap_err(),
+            "not valid binary or ASCII STL"
+        );
+        assert_eq!(
+            Mesh::from_stl(&stl[..84]).unwrap_err(),
+            "no triangles found"
+        );
+    }
+
+    #[test]
+    fn stl_binary_coordinates_must_be_finite() {
+        let square = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]];
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let stl = binary_stl(&[square, [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, bad, 0.0]]]);
+            assert_eq!(
+                Mesh::from_stl(&stl).unwrap_err(),
+                "triangle 2: a coordinate isn't a finite number"
+            );
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// parametric.rs
+//
+// Surfaces given as formulas for x, y and z in terms of u and v, so new ones can be drawn
+// without recompiling.
+
+use std::{f64::consts::TAU, ops::Range, str::FromStr};
+
+use crate::{
+    expr::Expression,
+    mesh::fitting,
+    surface::{numeric_normal, Sample, Surface},
+};
+
+// How many points across each of u and v to look at when working out how to fit the
+// surface to the frame
+const FIT_SAMPLES: u32 = 64;
+
+/// A surface given by a formula for each of x, y and z in terms of u and v, like the donut
+/// as `x = (2 + cos v) cos u; y = (2 + cos v) sin u; z = sin v`.
+///
+/// Normals are worked out numerically, and both sides of the surface are lit, since
+/// there's no telling which way round the formulas have it facing.
+#[derive(Clone, Debug)]
+pub struct Parametric {
+    coordinates: [Expression; 3],
+    domain: [Range<f64>; 2],
+    center: [f64; 3],
+    scale: f64,
+}
+
+impl Parametric {
+    /// Creates a surface from expressions for x, y and z, which should be parsed with the
+    /// variables `u` and `v` in that order. Both u and v go from 0 to 2π, unless
+    /// [`with_domain`](Parametric::with_domain) says otherwise.
+    pub fn new(coordinates: [Expression; 3]) -> Self {
+        Parametric {
+            coordinates,
+            domain: [0.0..TAU, 0.0..TAU],
+            center: [0.0; 3],
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the ranges of u and v to draw the surface over.
+    pub fn with_domain(mut self, u: Range<f64>, v: Range<f64>) -> Self {
+        self.domain = [u, v];
+        self
+    }
+
+    /// Moves and scales the surface to fit the frame, centered on the origin, the same way
+    /// [`Mesh::fitted`](crate::Mesh::fitted) does.
+    pub fn fitted(mut self) -> Self {
+        self.center = [0.0; 3];
+        self.scale = 1.0;
+
+        let [u_range, v_range] = self.domain.clone();
+        let along = |range: &Range<f64>, i: u32| {
+            range.start + (range.end - range.start) * f64::from(i) / f64::from(FIT_SAMPLES)
+        };
+        // Leave out anywhere the formulas don't work out, like the square root of something
+        // negative
+        let points: Vec<_> = (0..=FIT_SAMPLES)
+            .flat_map(|j| (0..=FIT_SAMPLES).map(move |i| (j, i)))
+            .map(|(j, i)| self.point(along(&u_range, j), along(&v_range, i)))
+            .filter(|point| point.iter().all(|n| n.is_finite()))
+            .collect();
+
+        (self.center, self.scale) = fitting(&points);
+        self
+    }
+
+    fn point(&self, u: f64, v: f64) -> [f64; 3] {
+        let [x, y, z] = &self.coordinates;
+        let point = [x, y, z].map(|coordinate| coordinate.evaluate(&[u, v]));
+        [0, 1, 2].map(|axis| (point[axis] - self.center[axis]) * self.scale)
+    }
+}
+
+impl Surface for Parametric {
+    fn domain(&self) -> [Range<f64>; 2] {
+        self.domain.clone()
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [160, 160]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        Sample {
+            point: self.point(u, v),
+            normal: numeric_normal(|u, v| self.point(u, v), u, v),
+        }
+    }
+
+    fn two_sided(&self) -> bool {
+        true
+    }
+}
+
+impl FromStr for Parametric {
+    type Err = String;
+
+    /// Parses formulas for x, y and z, and optionally ranges for u and v, separated by
+    /// semicolons, like `x = u; y = v; z = u^2 - v^2; u = -1..1; v = -1..1`. The formulas
+    /// are [`Expression`]s in u and v, and the ends of the ranges can be expressions too,
+    /// like `0..2pi`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut coordinates = [None, None, None];
+        let mut domain = [0.0..TAU, 0.0..TAU];
+
+        for statement in s.split([';', '\n']).map(str::trim) {
+            if statement.is_empty() {
+                continue;
+            }
+            let Some((name, value)) = statement.split_once('=') else {
+                return Err(format!("expected `name = value`, found `{statement}`"));
+            };
+
+            let name = name.trim();
+            match name {
+                "x" | "y" | "z" => {
+                    let axis = "xyz".find(name).unwrap_or_default();
+                    let expression = Expression::parse(value, &["u", "v"])
+                        .map_err(|error| format!("in the formula for {name}: {error}"))?;
+                    coordinates[axis] = Some(expression);
+                }
+                "u" | "v" => {
+                    let index = usize::from(name == "v");
+                    domain[index] = parse_range(value)
+                        .map_err(|error| format!("in the range of {name}: {error}"))?;
+                }
+                _ => return Err(format!("unknown name `{name}`, expected x, y, z, u or v")),
+            }
+        }
+
+        let [x, y, z] = coordinates;
+        let missing = |axis| format!("missing a formula for {axis}");
+        let coordinates = [
+            x.ok_or_else(|| missing("x"))?,
+            y.ok_or_else(|| missing("y"))?,
+            z.ok_or_else(|| missing("z"))?,
+        ];
+        let [u, v] = domain;
+        Ok(Parametric::new(coordinates).with_domain(u, v))
+    }
+}
+
+// Parses a range like `0..2pi`, with an expression at each end
+fn parse_range(s: &str) -> Result<Range<f64>, String> {
+    let Some((start, end)) = s.split_once("..") else {
+        return Err(format!(
+            "expected a range like `0..2pi`, found `{}`",
+            s.trim()
+        ));
+    };
+    let bound = |text: &str| -> Result<f64, String> {
+        let value = Expression::parse(text, &[])?.evaluate(&[]);
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(format!("`{}` isn't a number", text.trim()))
+        }
+    };
+
+    let range = bound(start)?..bound(end)?;
+    if range.start == range.end {
+        return Err("the range is empty".to_string());
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_formulas_and_ranges() {
+        let surface: Parametric = "x = u; y = v\nz = u v; u = -1..1; v = 0..pi"
+            .parse()
+            .unwrap();
+        assert_eq!(surface.domain, [-1.0..1.0, 0.0..std::f64::consts::PI]);
+        assert_eq!(surface.point(2.0, 3.0), [2.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn ranges_default_to_a_full_turn() {
+        let surface: Parametric = "x = cos u; y = sin u; z = v;".parse().unwrap();
+        assert_eq!(surface.domain, [0.0..TAU, 0.0..TAU]);
+    }
+
+    #[test]
+    fn reports_what_went_wrong() {
+        let error = |text: &str| text.parse::<Parametric>().unwrap_err();
+        assert_eq!(error("x = u; y = v"), "missing a formula for z");
+        assert_eq!(
+            error("x = u; y = v; z = 0; w = 1"),
+            "unknown name `w`, expected x, y, z, u or v"
+        );
+        assert_eq!(
+            error("x = u; y = v; z"),
+            "expected `name = value`, found `z`"
+        );
+        assert_eq!(
+            error("x = u; y = v; z = t"),
+            "in the formula for z: unknown name `t`, expected u or v, or a function like sin"
+        );
+        assert_eq!(
+            error("x = u; y = v; z = 0; u = 1"),
+            "in the range of u: expected a range like `0..2pi`, found `1`"
+        );
+        assert_eq!(
+            error("x = u; y = v; z = 0; v = 1..1"),
+            "in the range of v: the range is empty"
+        );
+        assert_eq!(
+            error("x = u; y = v; z = 0; v = 0..u"),
+            "in the range of v: unknown name `u`"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// parse.rs
+//
+// Small pieces of parsing shared by everything that reads settings written out as text,
+// from objects and solids to the command line and scene files.
+
+//! Helpers for reading settings written out as text.
+
+/// Parses three comma-separated numbers, like `0, 1.5, -2`.
+///
+/// Infinities and NaN are turned down along with anything that isn't a number at all,
+/// since they'd spread to every point they touched.
+pub fn vector(s: &str) -> Result<[f64; 3], &'static str> {
+    let numbers: Vec<f64> = s
+        .split(',')
+        .map(|n| n.trim().parse().ok().filter(|n: &f64| n.is_finite()))
+        .collect::<Option<_>>()
+        .ok_or("not a finite number")?;
+    numbers
+        .try_into()
+        .map_err(|_| "expected three numbers, for x, y and z")
+}
+
+/// Lists names like `a, b or c`, for saying what was expected.
+pub fn one_of(names: &[&str]) -> String {
+    match names {
+        [rest @ .., last] if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        _ => names.join(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors() {
+        assert_eq!(vector("0, 1.5,-2"), Ok([0.0, 1.5, -2.0]));
+        assert_eq!(vector(" 1e3 ,0,0 "), Ok([1000.0, 0.0, 0.0]));
+        assert_eq!(
+            vector("1, 2"),
+            Err("expected three numbers, for x, y and z")
+        );
+        assert_eq!(
+            vector("1, 2, 3, 4"),
+            Err("expected three numbers, for x, y and z")
+        );
+        assert_eq!(vector("1, x, 3"), Err("not a finite number"));
+        assert_eq!(vector("1, nan, 3"), Err("not a finite number"));
+        assert_eq!(vector("-inf, 0, 0"), Err("not a finite number"));
+        assert_eq!(vector(""), Err("not a finite number"));
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(one_of(&[]), "");
+        assert_eq!(one_of(&["a"]), "a");
+        assert_eq!(one_of(&["a", "b"]), "a or b");
+        assert_eq!(one_of(&["a", "b", "c"]), "a, b or c");
+    }
+}
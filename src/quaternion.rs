@@ -0,0 +1,295 @@
+// quaternion.rs
+//
+// Quaternions, for turning things about any axis at all, and blending smoothly between
+// one way round and another.
+
+use std::ops::Mul;
+
+use crate::mesh::{length, normalize};
+
+/// A rotation, stored as a unit quaternion.
+///
+/// Unlike a set of angles about fixed axes, a quaternion can turn about any axis without
+/// ever getting stuck where two of the axes line up, and two of them can be blended with
+/// [`slerp`](Quaternion::slerp) to turn smoothly from one to the other.
+///
+/// The parts are kept private so that it always stays a unit quaternion, which is what
+/// lets [`inverse`](Quaternion::inverse) undo it just by flipping the axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Quaternion {
+    /// The rotation that leaves everything as it is.
+    pub const IDENTITY: Quaternion = Quaternion {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    /// The rotation by `angle` radians about `axis`, which doesn't need to be normalized.
+    /// Turning about no axis at all leaves everything as it is.
+    pub fn from_axis_angle(axis: [f64; 3], angle: f64) -> Self {
+        let [x, y, z] = normalize(axis);
+        if [x, y, z] == [0.0; 3] {
+            return Self::IDENTITY;
+        }
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion {
+            w: cos,
+            x: x * sin,
+            y: y * sin,
+            z: z * sin,
+        }
+    }
+
+    /// The rotation about the axis `vector` points along, by as many radians as it's long.
+    /// An angular velocity times how long it's been going gives how far it's turned.
+    pub fn from_rotation_vector(vector: [f64; 3]) -> Self {
+        Self::from_axis_angle(vector, length(vector))
+    }
+
+    /// The rotation about the x-axis, then the y-axis, then the z-axis by each of `angles`
+    /// in turn, in radians.
+    pub fn from_euler([x, y, z]: [f64; 3]) -> Self {
+        Self::from_axis_angle([0.0, 0.0, 1.0], z)
+            * Self::from_axis_angle([0.0, 1.0, 0.0], y)
+            * Self::from_axis_angle([1.0, 0.0, 0.0], x)
+    }
+
+    /// The rotation that undoes this one.
+    pub fn inverse(self) -> Self {
+        Quaternion {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    /// Scales the quaternion back to unit length, to undo any rounding that's crept in.
+    pub fn normalized(self) -> Self {
+        let length = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if length > f64::EPSILON {
+            Quaternion {
+                w: self.w / length,
+                x: self.x / length,
+                y: self.y / length,
+                z: self.z / length,
+            }
+        } else {
+            Self::IDENTITY
+        }
+    }
+
+    /// Turns from this rotation towards `other` at a steady rate, the shortest way round,
+    /// with `t` running from 0.0 (all `self`) to 1.0 (all `other`).
+    pub fn slerp(self, other: Quaternion, t: f64) -> Self {
+        let mut dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        // A quaternion and its negative are the same rotation, so go towards whichever is
+        // nearer
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Quaternion {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            other
+        };
+
+        // Nearly the same rotation, where the angle between them is too small to divide by
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+        .normalized()
+    }
+
+    /// Turns a vector by the rotation.
+    pub fn rotate(self, vector: [f64; 3]) -> [f64; 3] {
+        self.to_matrix()
+            .map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+    }
+
+    /// The rotation as a matrix, to multiply column vectors by.
+    pub fn to_matrix(self) -> [[f64; 3]; 3] {
+        let Quaternion { w, x, y, z } = self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// The rotation that does `other` and then `self`.
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2, TAU};
+
+    const X: [f64; 3] = [1.0, 0.0, 0.0];
+    const Y: [f64; 3] = [0.0, 1.0, 0.0];
+    const Z: [f64; 3] = [0.0, 0.0, 1.0];
+
+    fn assert_near(found: [f64; 3], expected: [f64; 3]) {
+        assert!(
+            (0..3).all(|axis| (found[axis] - expected[axis]).abs() < 1e-9),
+            "expected {expected:?}, found {found:?}"
+        );
+    }
+
+    fn quarter_turn(axis: [f64; 3]) -> Quaternion {
+        Quaternion::from_axis_angle(axis, FRAC_PI_2)
+    }
+
+    #[test]
+    fn turns_about_an_axis() {
+        // Anticlockwise looking down the axis, by the right-hand rule
+        assert_near(quarter_turn(Z).rotate(X), Y);
+        assert_near(quarter_turn(X).rotate(Y), Z);
+        assert_near(quarter_turn(Y).rotate(Z), X);
+
+        // The axis doesn't need to be normalized, and it's still a unit quaternion
+        let turn = Quaternion::from_axis_angle([0.0, 0.0, 5.0], FRAC_PI_2);
+        assert_eq!(turn, quarter_turn(Z));
+        assert_near(
+            Quaternion::from_axis_angle([1.0, 1.0, 1.0], TAU / 3.0).rotate(X),
+            Y,
+        );
+
+        // No axis at all, or no angle, is no turn
+        assert_eq!(
+            Quaternion::from_axis_angle([0.0; 3], 1.0),
+            Quaternion::IDENTITY
+        );
+        assert_near(Quaternion::from_rotation_vector([0.0; 3]).rotate(X), X);
+        assert_near(
+            Quaternion::from_rotation_vector([0.0, 0.0, FRAC_PI_2]).rotate(X),
+            Y,
+        );
+    }
+
+    #[test]
+    fn euler_angles_go_x_then_y_then_z() {
+        // About x first takes y up to z, which the turn about z then leaves alone. The
+        // other way round, z would take y over to -x, which x would leave alone.
+        let turn = Quaternion::from_euler([FRAC_PI_2, 0.0, FRAC_PI_2]);
+        assert_near(turn.rotate(Y), Z);
+
+        // About y first takes x down to -z, where the turn about z leaves it
+        let turn = Quaternion::from_euler([0.0, FRAC_PI_2, FRAC_PI_2]);
+        assert_near(turn.rotate(X), [0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn multiplying_does_the_right_hand_side_first() {
+        let (z, x) = (quarter_turn(Z), quarter_turn(X));
+        assert_near((z * x).rotate(X), Y);
+        assert_near((x * z).rotate(X), Z);
+
+        let v = [0.3, -1.2, 2.0];
+        assert_near((z * x).rotate(v), z.rotate(x.rotate(v)));
+    }
+
+    #[test]
+    fn inverse_undoes_the_rotation() {
+        let turn = Quaternion::from_euler([0.4, -1.1, 2.5]);
+        let v = [0.3, -1.2, 2.0];
+        assert_near(turn.inverse().rotate(turn.rotate(v)), v);
+        assert_near((turn * turn.inverse()).rotate(v), v);
+    }
+
+    #[test]
+    fn slerp_ends_and_middle() {
+        let (start, end) = (Quaternion::IDENTITY, quarter_turn(Z));
+        assert_near(start.slerp(end, 0.0).rotate(X), X);
+        assert_near(start.slerp(end, 1.0).rotate(X), Y);
+
+        // Halfway there is half the turn, and a quarter of the way a quarter of it
+        let diagonal = [SQRT_2 / 2.0, SQRT_2 / 2.0, 0.0];
+        assert_near(start.slerp(end, 0.5).rotate(X), diagonal);
+        let eighth = (FRAC_PI_4 / 2.0).sin_cos();
+        assert_near(start.slerp(end, 0.25).rotate(X), [eighth.1, eighth.0, 0.0]);
+    }
+
+    #[test]
+    fn slerp_goes_the_short_way_round() {
+        // The same rotation as a quarter turn about z, but with every part negated, which
+        // is on the far side of the sphere from the identity
+        let turn = quarter_turn(Z);
+        let negated = Quaternion {
+            w: -turn.w,
+            x: -turn.x,
+            y: -turn.y,
+            z: -turn.z,
+        };
+        assert_near(negated.rotate(X), Y);
+
+        let diagonal = [SQRT_2 / 2.0, SQRT_2 / 2.0, 0.0];
+        assert_near(Quaternion::IDENTITY.slerp(negated, 0.5).rotate(X), diagonal);
+    }
+
+    #[test]
+    fn slerp_between_nearly_the_same_rotations() {
+        let start = quarter_turn(Z);
+        let end = Quaternion::from_axis_angle(Z, FRAC_PI_2 + 1e-7);
+        let middle = start.slerp(end, 0.5);
+
+        let length =
+            (middle.w * middle.w + middle.x * middle.x + middle.y * middle.y + middle.z * middle.z)
+                .sqrt();
+        assert!((length - 1.0).abs() < 1e-12);
+        let (sin, cos) = (FRAC_PI_2 + 0.5e-7).sin_cos();
+        assert_near(middle.rotate(X), [cos, sin, 0.0]);
+    }
+}
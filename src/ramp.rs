@@ -0,0 +1,107 @@
+// ramp.rs
+//
+// The characters used to show luminance, from darkest to brightest.
+
+use std::str::FromStr;
+
+/// A luminance ramp: a run of characters from darkest to brightest, which luminance gets
+/// quantized onto however many there are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ramp {
+    glyphs: Vec<char>,
+}
+
+impl Ramp {
+    /// The original twelve characters from `donut.c`.
+    pub const CLASSIC: &'static str = ".,-~:;=!*#$@";
+    /// Unicode shading blocks.
+    pub const BLOCKS: &'static str = "░▒▓█";
+    /// Paul Bourke's 70-character ASCII ramp, with the space as its darkest level.
+    pub const LONG: &'static str =
+        " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$";
+    /// The digits 0 to 9.
+    pub const DIGITS: &'static str = "0123456789";
+
+    /// Creates a ramp from the given characters, darkest first. Returns `None` if there aren't any.
+    pub fn new(glyphs: &str) -> Option<Ramp> {
+        let glyphs: Vec<char> = glyphs.chars().collect();
+        (!glyphs.is_empty()).then_some(Ramp { glyphs })
+    }
+
+    /// Looks up one of the built-in ramps by name: `classic`, `blocks`, `long` or `digits`.
+    pub fn named(name: &str) -> Option<Ramp> {
+        let glyphs = match name {
+            "classic" => Ramp::CLASSIC,
+            "blocks" => Ramp::BLOCKS,
+            "long" => Ramp::LONG,
+            "digits" => Ramp::DIGITS,
+            _ => return None,
+        };
+        Ramp::new(glyphs)
+    }
+
+    /// Picks the character for a luminance between 0.0 and 1.0.
+    pub fn glyph(&self, luminance: f32) -> char {
+        let index = (luminance.clamp(0.0, 1.0) * self.glyphs.len() as f32) as usize;
+        self.glyphs[index.min(self.glyphs.len() - 1)]
+    }
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp {
+            glyphs: Ramp::CLASSIC.chars().collect(),
+        }
+    }
+}
+
+impl FromStr for Ramp {
+    type Err = String;
+
+    /// Parses either the name of a built-in ramp, or the characters of a custom one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ramp::named(s)
+            .or_else(|| Ramp::new(s))
+            .ok_or_else(|| "a ramp needs at least one character".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        assert_eq!("classic".parse(), Ok(Ramp::default()));
+        assert_eq!("blocks".parse(), Ok(Ramp::new("░▒▓█").unwrap()));
+        // Anything that isn't a name is taken as the characters themselves
+        assert_eq!("long-ish".parse(), Ok(Ramp::new("long-ish").unwrap()));
+        assert_eq!(
+            "".parse::<Ramp>(),
+            Err("a ramp needs at least one character".to_string())
+        );
+        assert_eq!(Ramp::new(""), None);
+        assert_eq!(Ramp::named("custom"), None);
+    }
+
+    #[test]
+    fn luminance_to_glyphs() {
+        let ramp = Ramp::new("abcd").unwrap();
+        assert_eq!(ramp.glyph(0.0), 'a');
+        assert_eq!(ramp.glyph(0.24), 'a');
+        assert_eq!(ramp.glyph(0.25), 'b');
+        assert_eq!(ramp.glyph(0.5), 'c');
+        assert_eq!(ramp.glyph(0.99), 'd');
+        assert_eq!(ramp.glyph(1.0), 'd');
+
+        // Anything out of range goes to the nearest end, and NaN to the darkest
+        assert_eq!(ramp.glyph(-1.0), 'a');
+        assert_eq!(ramp.glyph(2.0), 'd');
+        assert_eq!(ramp.glyph(f32::NAN), 'a');
+
+        let ramp = Ramp::named("blocks").unwrap();
+        assert_eq!((ramp.glyph(0.0), ramp.glyph(1.0)), ('░', '█'));
+        let ramp = Ramp::new("#").unwrap();
+        assert_eq!((ramp.glyph(0.0), ramp.glyph(1.0)), ('#', '#'));
+    }
+}
@@ -0,0 +1,356 @@
+// renderer.rs
+//
+// The fixed-point donut renderer, based on "donut.c without a math library".
+
+use crate::frame::{Frame, HEIGHT, WIDTH};
+
+// The distance from the viewer to the centre of the donut, K2 in the original code
+const DISTANCE: f32 = 5.0;
+
+// How much fixed-point luminance makes up the whole range from darkest to brightest: the
+// original's twelve characters, each covering 128 of it
+const LUMINANCE_RANGE: f32 = 12.0 * 128.0;
+
+// Reimplementing the R(mul,shift,x,y) function from the original code
+fn rotate(multiplier: i32, shift: i32, x: &mut i32, y: &mut i32) {
+    let mut temp: i32 = *x;
+    *x -= (multiplier * *y) >> shift;
+    *y += (multiplier * temp) >> shift;
+    temp = (3145728 - *x * *x - *y * *y) >> 11;
+    *x = (*x * temp) >> 10;
+    *y = (*y * temp) >> 10;
+}
+
+// Converts an angle in radians to the fixed-point sine and cosine used by the renderer,
+// where 1024 represents 1.0.
+fn fixed_sin_cos(angle: f64) -> (i32, i32) {
+    let (sin, cos) = angle.sin_cos();
+    ((sin * 1024.0).round() as i32, (cos * 1024.0).round() as i32)
+}
+
+// The shift used with `rotate()` when advancing angles A and B. It's large enough
+// that the original `rotate(5, 7, ...)` and `rotate(5, 8, ...)` speeds convert exactly.
+const SPEED_SHIFT: i32 = 16;
+
+// Converts a rotation speed in radians per frame to a multiplier for `rotate()`
+fn fixed_speed(speed: f64) -> i32 {
+    (speed * f64::from(1 << SPEED_SHIFT)).round() as i32
+}
+
+// The original 90 by 324 samples cover a frame the size of the original grid fine,
+// but leave gaps in anything much bigger. Each doubling of the density halves the
+// steps taken around the donut, and doubles how many of them there are.
+pub(crate) fn density(scale: f64) -> i32 {
+    let mut density = 0;
+    while f64::from(324 << density) < scale * 6.0 && density < 4 {
+        density += 1;
+    }
+    density
+}
+
+/// The rotation speed of angle A in `donut.c`, in radians per frame.
+pub const DEFAULT_SPEED_A: f64 = 5.0 / 128.0;
+/// The rotation speed of angle B in `donut.c`, in radians per frame.
+pub const DEFAULT_SPEED_B: f64 = 5.0 / 256.0;
+/// The fastest angles A and B can turn, in radians per frame, either way round.
+///
+/// The original advances its angles a small step at a time, which bends the donut out of
+/// shape once the steps get big, and overflows its fixed-point numbers altogether a little
+/// past 1.5 radians. Up to here the donut stays within half a percent of its proper size.
+pub const MAX_SPEED: f64 = 0.25;
+
+/// Renders the spinning donut one frame at a time.
+///
+/// The renderer keeps track of the two rotation angles, A and B, as fixed-point sines and
+/// cosines, exactly like the original code does. The fixed-point math only knows how to
+/// draw the donut; [`FloatRenderer`](crate::FloatRenderer) can draw any other
+/// [`Surface`](crate::Surface).
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    sin_a: i32,
+    cos_a: i32,
+    sin_b: i32,
+    cos_b: i32,
+    speed_a: i32,
+    speed_b: i32,
+    grid: Grid,
+}
+
+// The size of the frames to render, along with the shape of their pixels
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Grid {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) aspect: f64,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid {
+            width: WIDTH,
+            height: HEIGHT,
+            aspect: 2.0,
+        }
+    }
+}
+
+impl Grid {
+    // The original code centres the donut at (40, 12) and scales it by 30 horizontally and
+    // 15 vertically, which only suits an 80x22 grid. Instead, centre it on whatever grid
+    // we've been given, and pick the largest scale that still fits it both ways. This is
+    // the horizontal scale; pixels are usually taller than they are wide, so the vertical
+    // scale is this divided by the aspect ratio.
+    pub(crate) fn scale(&self) -> f64 {
+        (self.width as f64 * 30.0 / 80.0).min(self.height as f64 * self.aspect * 15.0 / 22.0)
+    }
+}
+
+/// The interface shared by all the renderers, so they can be swapped for one another.
+pub trait Render {
+    /// Renders a frame at the renderer's current orientation.
+    fn render(&self) -> Frame;
+
+    /// Renders a frame rotated by `angle_a` and `angle_b`, both in radians, without
+    /// affecting the renderer's current orientation.
+    fn render_frame(&self, angle_a: f64, angle_b: f64) -> Frame;
+
+    /// Advances the rotation by one frame's worth.
+    fn advance(&mut self);
+
+    /// Changes the size of the frames to render from now on, in pixels.
+    fn resize(&mut self, width: usize, height: usize);
+}
+
+impl Renderer {
+    /// Creates a renderer at the same starting orientation as `donut.c`.
+    pub fn new() -> Self {
+        Renderer {
+            sin_a: 1024,
+            cos_a: 0,
+            sin_b: 1024,
+            cos_b: 0,
+            speed_a: fixed_speed(DEFAULT_SPEED_A),
+            speed_b: fixed_speed(DEFAULT_SPEED_B),
+            grid: Grid::default(),
+        }
+    }
+
+    /// Sets the size of the frames to render, in pixels. The donut is scaled and
+    /// centred to fit whatever size is given.
+    pub fn with_size(mut self, width: usize, height: usize) -> Self {
+        self.resize(width, height);
+        self
+    }
+
+    /// Sets how many times taller than it is wide each pixel of the frame will be shown.
+    /// This defaults to 2.0, which suits one pixel per character of a typical terminal font.
+    pub fn with_aspect(mut self, aspect: f64) -> Self {
+        self.grid.aspect = aspect;
+        self
+    }
+
+    /// Sets how far angles A and B advance with each call to [`advance`](Self::advance),
+    /// in radians per frame. Speeds beyond [`MAX_SPEED`] either way are held to it.
+    pub fn with_speed(mut self, speed_a: f64, speed_b: f64) -> Self {
+        self.speed_a = fixed_speed(speed_a.clamp(-MAX_SPEED, MAX_SPEED));
+        self.speed_b = fixed_speed(speed_b.clamp(-MAX_SPEED, MAX_SPEED));
+        self
+    }
+
+    /// Changes the size of the frames to render from now on, in pixels.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid.width = width;
+        self.grid.height = height;
+    }
+
+    /// Renders a frame at the renderer's current orientation.
+    pub fn render(&self) -> Frame {
+        draw(self.grid, self.sin_a, self.cos_a, self.sin_b, self.cos_b)
+    }
+
+    /// Renders a frame with the donut rotated by `angle_a` and `angle_b`, both in radians.
+    /// This doesn't affect the renderer's current orientation.
+    pub fn render_frame(&self, angle_a: f64, angle_b: f64) -> Frame {
+        let (sin_a, cos_a) = fixed_sin_cos(angle_a);
+        let (sin_b, cos_b) = fixed_sin_cos(angle_b);
+        draw(self.grid, sin_a, cos_a, sin_b, cos_b)
+    }
+
+    /// Advances the rotation by one frame's worth.
+    pub fn advance(&mut self) {
+        rotate(self.speed_a, SPEED_SHIFT, &mut self.cos_a, &mut self.sin_a);
+        rotate(self.speed_b, SPEED_SHIFT, &mut self.cos_b, &mut self.sin_b);
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for Renderer {
+    fn render(&self) -> Frame {
+        Renderer::render(self)
+    }
+
+    fn render_frame(&self, angle_a: f64, angle_b: f64) -> Frame {
+        Renderer::render_frame(self, angle_a, angle_b)
+    }
+
+    fn advance(&mut self) {
+        Renderer::advance(self)
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        Renderer::resize(self, width, height)
+    }
+}
+
+// Because the variable names herein have been updated from the original C names,
+// I've forcibly joined two differing styles. As such, I've opted to suppress
+// the warning that variable names should be in snake case.
+#[allow(non_snake_case)]
+fn draw(grid: Grid, sin_A: i32, cos_A: i32, sin_B: i32, cos_B: i32) -> Frame {
+    let Grid { width, height, .. } = grid;
+    let mut frame = Frame::new(width, height);
+
+    // The aspect ratio gets kept as a fixed-point number, like everything else
+    let center_x = (width / 2) as i64;
+    let center_y = (height / 2) as i64;
+    let scale = grid.scale() as i64;
+    let aspect = (grid.aspect * 1024.0).round().max(1.0) as i64;
+
+    let density = density(scale as f64);
+
+    for &(sin_j, cos_j) in &steps(90, 9, 7, density) {
+        for &(sin_i, cos_i) in &steps(324, 5, 8, density) {
+            // The variables here have their original names appended to the end of
+            // their new names for easy identification in the original source code.
+            let minor_radius_r1: i32 = 1;
+            let major_radius_r2: i32 = 2048;
+            let distance_constant_k2: i32 = 5120 * 1024;
+
+            // I decided to forgo the type annotations for the eight successive
+            // variables, but as you may guess, they are all i32 types.
+            let x0 = minor_radius_r1 * cos_j + major_radius_r2;
+            let x1 = (cos_i * x0) >> 10;
+            let x2 = (cos_A * sin_j) >> 10;
+            let x3 = (sin_i * x0) >> 10;
+            let x4 = minor_radius_r1 * x2 - ((sin_A * x3) >> 10);
+            let x5 = (sin_A * sin_j) >> 10;
+            let x6 = distance_constant_k2 + minor_radius_r1 * 1024 * x5 + cos_A * x3;
+            let x7 = (cos_j * sin_i) >> 10;
+
+            // These get calculated as i64 types, as large terminals would otherwise
+            // overflow an i32 once the scale gets multiplied in.
+            let x: i64 = center_x + scale * i64::from(cos_B * x1 - sin_B * x4) / i64::from(x6);
+            let y: i64 = center_y
+                + scale * 1024 * i64::from(cos_B * x4 + sin_B * x1) / (aspect * i64::from(x6));
+
+            // The luminance here is in the same fixed-point scale as everything else, and
+            // ranges from -sqrt(2) to sqrt(2). The original picks one of its twelve
+            // characters with `luminance >> 7`, so scale it down by twelve steps of 128,
+            // aiming for the middle of each step so the classic ramp picks the very same
+            // characters. Points facing away from the light get clamped to 0.0 by the frame.
+            let luminance: i32 = (((-cos_A * x7)
+                - cos_B * (((-sin_A * x7) >> 10) + x2)
+                - cos_i * ((cos_j * sin_B) >> 10))
+                >> 10)
+                - x5;
+            let luminance: f32 = (luminance as f32 + 0.5) / LUMINANCE_RANGE;
+
+            let zz: i8 = i8::try_from((x6 - distance_constant_k2) >> 15)
+                .expect("Couldn't convert zz to i8!");
+
+            if (0..height as i64).contains(&y) && (0..width as i64).contains(&x) {
+                // `zz` is the distance from the centre of the donut in 32nds of a unit,
+                // so this turns it back into a distance from the viewer
+                let depth = DISTANCE + f32::from(zz) / 32.0;
+
+                // Both coordinates are known to be positive here, so the conversion is safe
+                frame.plot(x as usize, y as usize, luminance, depth);
+            }
+        }
+    }
+
+    frame
+}
+
+// The fixed-point sines and cosines of the angles to sample around the donut at: `count`
+// steps of `multiplier >> shift` radians, as the original takes with `rotate()`. Finer
+// densities take more, smaller steps over the same sweep, but halving the step with a
+// bigger shift leaves `rotate()` rounding away most of each one, and the angles drift
+// until whole columns get skipped. So past the original density, each angle gets worked
+// out afresh instead.
+fn steps(count: usize, multiplier: i32, shift: i32, density: i32) -> Vec<(i32, i32)> {
+    if density == 0 {
+        let (mut sin, mut cos) = (0, 1024);
+        (0..count)
+            .map(|_| {
+                let step = (sin, cos);
+                rotate(multiplier, shift, &mut cos, &mut sin);
+                step
+            })
+            .collect()
+    } else {
+        let step = f64::from(multiplier) / f64::from(1 << (shift + density));
+        (0..count << density)
+            .map(|k| fixed_sin_cos(k as f64 * step))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The first frame `donut.c` draws, character for character
+    const ORIGINAL_FIRST_FRAME: [&str; HEIGHT] = [
+        "",
+        "",
+        "                                  $@@$$@@$$$@@$",
+        "                              $$$$$###########$$$$$",
+        "                            $####**!!!!!!!!!!!**###$$",
+        "                          ####**!===============!**####",
+        "                        *##**!!!===;::::::::;;===!!!*###*",
+        "                       !****!!===;:~--,,.,,-~~:;===!!*****",
+        "                      =****!!==;;:-,........,--:;;=!!!****=",
+        "                      !***!!!==;:--...........-~:;=!!!!***!",
+        "                      !***!!!=;;:-,..       ..,-:;==!!!**!!",
+        "                     ;!!**!!!==;:-,.         ~;==!!!!**!!!=:",
+        "                      =!!!****!*!!!==       =!!********!!!=",
+        "                      ;=!!!*****#*#####***########****!!==;",
+        "                      -;=!!****####$$$$$@@@$$$$###****!!=;-",
+        "                       ~:=!!****###$$$$@@@@$$$$###**!!==;-",
+        "                        ,:;=!!**####$$$$$$$$$####**!!=;:-",
+        "                          -:;=!!!**###########**!!!=;:-",
+        "                            -:;;==!!!*******!!!==;::-",
+        "                              .-~::;;=======;;::~-.",
+        "                                  ..,,-----,,,.",
+        "",
+    ];
+
+    // A frame big enough to sample the donut more finely than the original does, without
+    // leaving any gaps a single cell wide across it
+    #[test]
+    fn large_frame_has_no_holes() {
+        let renderer = Renderer::new().with_size(200, 60);
+        assert!(density(renderer.grid.scale()) > 0);
+        let frame = renderer.render();
+        let covered = |x: usize, y: usize| frame.pixel(x, y).is_some();
+        for y in 0..frame.height() {
+            for x in 1..frame.width() - 1 {
+                let between = covered(x - 1, y) && covered(x + 1, y);
+                assert!(!between || covered(x, y), "hole at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn first_frame_matches_the_original() {
+        let text = Renderer::new().render().to_string();
+        let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+        assert_eq!(lines, ORIGINAL_FIRST_FRAME);
+    }
+}
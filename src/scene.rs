@@ -0,0 +1,412 @@
+// scene.rs
+//
+// Scenes of several objects drawn together, each with its own place, size and spin.
+
+use std::{f64::consts::TAU, str::FromStr, sync::Arc};
+
+use crate::{
+    cloud::PointCloud,
+    implicit::Implicit,
+    light::Material,
+    mesh::Mesh,
+    parse::{one_of, vector},
+    quaternion::Quaternion,
+    sdf::Solid,
+    shapes::Shape,
+    surface::Surface,
+};
+
+// How many frames an object takes to swing out and back, unless it says otherwise
+const DEFAULT_PERIOD: f64 = 100.0;
+
+/// A set of objects drawn together, hiding each other wherever they overlap. The scene as
+/// a whole still tumbles by angles A and B like the donut, and each object can move and
+/// spin within it on top of that.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    objects: Vec<Object>,
+}
+
+impl Scene {
+    /// Creates an empty scene.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an object to the scene.
+    pub fn with_object(mut self, object: Object) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// The objects at the top of the scene, not counting their children.
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// Every object in the scene, children and all, along with where it is after `time`
+    /// frames.
+    pub(crate) fn placements(&self, time: f64) -> Vec<(&Object, Placement)> {
+        let mut placements = Vec::new();
+        for object in &self.objects {
+            object.place(time, None, &mut placements);
+        }
+        placements
+    }
+}
+
+/// Something to draw in a [`Scene`], and where.
+///
+/// Each object is scaled, then turned to its [`rotation`](Object::with_rotation), then
+/// moved out to its [`position`](Object::with_position), and can keep turning about its
+/// own center or swinging around the center of the scene as the frames go by, about any
+/// axis at all. It can also [swing](Object::with_swing) back and forth between its
+/// rotation and another one. Its [`Material`] says how it's shaded.
+///
+/// An object can have [children](Object::with_child), which go wherever it goes and turn
+/// however it turns, like a moon going around a planet as the planet goes around the sun.
+/// Their own position, rotation, spin and orbit are all relative to their parent, but
+/// their scale isn't, so a parent can be resized without pushing its children away.
+#[derive(Clone, Debug)]
+pub struct Object {
+    pub(crate) model: Model,
+    pub(crate) material: Material,
+    position: [f64; 3],
+    scale: f64,
+    rotation: Quaternion,
+    spin: [f64; 3],
+    orbit: [f64; 3],
+    swing: Option<Quaternion>,
+    period: f64,
+    children: Vec<Object>,
+}
+
+// What an object draws
+#[derive(Clone, Debug)]
+pub(crate) enum Model {
+    Surface(Arc<dyn Surface + Send + Sync>),
+    Mesh(Arc<Mesh>),
+    Cloud(Arc<PointCloud>),
+    Solid(Arc<Solid>),
+    Implicit(Arc<Implicit>),
+}
+
+impl Object {
+    /// The names of everything about an object that
+    /// [`with_setting`](Object::with_setting) can change.
+    pub const SETTINGS: [&'static str; 10] = [
+        "position", "scale", "rotation", "spin", "orbit", "swing", "period", "color", "ambient",
+        "diffuse",
+    ];
+
+    fn new(model: Model) -> Self {
+        Object {
+            model,
+            material: Material::default(),
+            position: [0.0; 3],
+            scale: 1.0,
+            rotation: Quaternion::IDENTITY,
+            spin: [0.0; 3],
+            orbit: [0.0; 3],
+            swing: None,
+            period: DEFAULT_PERIOD,
+            children: Vec::new(),
+        }
+    }
+
+    /// An object that samples a surface, like one of the built-in [`Shape`]s.
+    pub fn surface(surface: impl Surface + Send + Sync + 'static) -> Self {
+        Self::new(Model::Surface(Arc::new(surface)))
+    }
+
+    /// An object that draws a triangle mesh.
+    pub fn mesh(mesh: Mesh) -> Self {
+        Self::new(Model::Mesh(Arc::new(mesh)))
+    }
+
+    /// An object that draws a point cloud.
+    pub fn point_cloud(cloud: PointCloud) -> Self {
+        Self::new(Model::Cloud(Arc::new(cloud)))
+    }
+
+    /// An object that raymarches a solid.
+    pub fn solid(solid: Solid) -> Self {
+        Self::new(Model::Solid(Arc::new(solid)))
+    }
+
+    /// An object that raymarches an implicit surface.
+    pub fn implicit(implicit: Implicit) -> Self {
+        Self::new(Model::Implicit(Arc::new(implicit)))
+    }
+
+    /// Sets where the center of the object sits in the scene.
+    pub fn with_position(mut self, position: [f64; 3]) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets how many times bigger to draw the object than it was made.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets how the object starts out turned: about the x-axis, then the y-axis, then the
+    /// z-axis by each of `angles` in turn, in radians.
+    pub fn with_rotation(self, angles: [f64; 3]) -> Self {
+        self.with_orientation(Quaternion::from_euler(angles))
+    }
+
+    /// Sets how the object starts out turned, as any rotation at all.
+    pub fn with_orientation(mut self, rotation: Quaternion) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets how fast the object turns about its own center, as an angular velocity: it
+    /// turns about the axis `spin` points along, at as many radians per frame as it's long.
+    pub fn with_spin(mut self, spin: [f64; 3]) -> Self {
+        self.spin = spin;
+        self
+    }
+
+    /// Sets how fast the object swings around the center of the scene, as an angular
+    /// velocity like [`with_spin`](Object::with_spin).
+    pub fn with_orbit(mut self, orbit: [f64; 3]) -> Self {
+        self.orbit = orbit;
+        self
+    }
+
+    /// Sets the object swinging back and forth between its rotation and `rotation`, easing
+    /// in and out at each end like a pendulum, and taking `period` frames to get there and
+    /// back.
+    pub fn with_swing(mut self, rotation: Quaternion, period: f64) -> Self {
+        self.swing = Some(rotation);
+        self.period = period;
+        self
+    }
+
+    /// Sets how the object is shaded.
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Adds an object that moves and turns along with this one.
+    pub fn with_child(mut self, child: Object) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// The objects that move and turn along with this one.
+    pub fn children(&self) -> &[Object] {
+        &self.children
+    }
+
+    /// Changes one of the object's settings, given by name and written out as text the
+    /// way [`from_str`](Object::from_str) parses it: `position`, `scale`, `rotation`,
+    /// `spin`, `orbit`, `swing` or its `period`, or the `color`, `ambient` light or
+    /// `diffuse` light of its material.
+    pub fn with_setting(mut self, name: &str, value: &str) -> Result<Self, String> {
+        let error = |error| format!("invalid {name} `{value}`: {error}");
+        let fraction = || {
+            value
+                .parse()
+                .ok()
+                .filter(|n: &f64| n.is_finite() && *n >= 0.0)
+                .ok_or_else(|| error("not a number from 0 up"))
+        };
+
+        match name {
+            "position" => self.position = vector(value).map_err(error)?,
+            "scale" => {
+                self.scale = value
+                    .parse()
+                    .ok()
+                    .filter(|scale: &f64| scale.is_finite() && *scale > 0.0)
+                    .ok_or_else(|| error("not a positive number"))?
+            }
+            "rotation" => self.rotation = parse_rotation(value).map_err(error)?,
+            "spin" => self.spin = vector(value).map_err(error)?,
+            "orbit" => self.orbit = vector(value).map_err(error)?,
+            "swing" => self.swing = Some(parse_rotation(value).map_err(error)?),
+            "period" => {
+                self.period = value
+                    .parse()
+                    .ok()
+                    .filter(|period: &f64| period.is_finite() && *period > 0.0)
+                    .ok_or_else(|| error("not a positive number"))?
+            }
+            "color" => self.material.color = Some(value.parse()?),
+            "ambient" => self.material.ambient = fraction()?,
+            "diffuse" => self.material.diffuse = fraction()?,
+            _ => {
+                return Err(format!(
+                    "unknown setting `{name}`, expected {}",
+                    one_of(&Self::SETTINGS)
+                ))
+            }
+        }
+        Ok(self)
+    }
+
+    /// Where the object is, how big it is and which way it's turned after `time` frames.
+    pub(crate) fn placement(&self, time: f64) -> Placement {
+        let turned =
+            |velocity: [f64; 3]| Quaternion::from_rotation_vector(velocity.map(|n| n * time));
+        let rotation = match self.swing {
+            Some(swing) => {
+                let t = (1.0 - (TAU * time / self.period).cos()) / 2.0;
+                self.rotation.slerp(swing, t)
+            }
+            None => self.rotation,
+        };
+        Placement {
+            rotation: turned(self.spin) * rotation,
+            scale: self.scale,
+            position: turned(self.orbit).rotate(self.position),
+        }
+    }
+
+    // Adds this object and all its children to `placements`, put in place within whatever
+    // `parent` they belong to
+    fn place<'a>(
+        &'a self,
+        time: f64,
+        parent: Option<Placement>,
+        placements: &mut Vec<(&'a Object, Placement)>,
+    ) {
+        let own = self.placement(time);
+        let placement = match parent {
+            Some(parent) => Placement {
+                rotation: parent.rotation * own.rotation,
+                scale: own.scale,
+                position: {
+                    let turned = parent.rotation.rotate(own.position);
+                    [0, 1, 2].map(|axis| turned[axis] + parent.position[axis])
+                },
+            },
+            None => own,
+        };
+
+        placements.push((self, placement));
+        for child in &self.children {
+            child.place(time, Some(placement), placements);
+        }
+    }
+}
+
+impl FromStr for Object {
+    type Err = String;
+
+    /// Parses an object as a list of settings separated by semicolons, like
+    /// `shape = torus; position = 0, 0, 1; scale = 0.5; spin = 0, 0, 0.05`. The object is
+    /// either a built-in `shape` or a `solid` to raymarch, written the way
+    /// [`Solid`](Solid#impl-FromStr-for-Solid) parses it. The `rotation` is in degrees,
+    /// either about the x, y and z axes in turn or about an axis of its own, like
+    /// `rotation = 120 about 1, 1, 1`. The `spin` and `orbit` are angular velocities, in
+    /// radians per frame like the speeds of angles A and B. The object can `swing` to
+    /// another rotation and back every `period` frames, and have a `color`, as a hex
+    /// code, and how much `ambient` and `diffuse` light it gives back.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut model = None;
+        let mut settings = Vec::new();
+
+        for statement in s.split([';', '\n']).map(str::trim) {
+            if statement.is_empty() {
+                continue;
+            }
+            let Some((name, value)) = statement.split_once('=') else {
+                return Err(format!("expected `name = value`, found `{statement}`"));
+            };
+
+            match (name.trim(), value.trim()) {
+                ("shape" | "solid", _) if model.is_some() => {
+                    return Err("an object can only have one shape or solid".to_string())
+                }
+                ("shape", value) => model = Some(Model::Surface(Arc::new(value.parse::<Shape>()?))),
+                ("solid", value) => model = Some(Model::Solid(Arc::new(value.parse()?))),
+                (name, value) if Object::SETTINGS.contains(&name) => settings.push((name, value)),
+                (name, _) => {
+                    return Err(format!(
+                        "unknown setting `{name}`, expected shape, solid, {}",
+                        one_of(&Object::SETTINGS)
+                    ))
+                }
+            }
+        }
+
+        let model = model.ok_or_else(|| "an object needs a shape or a solid".to_string())?;
+        settings
+            .into_iter()
+            .try_fold(Object::new(model), |object, (name, value)| {
+                object.with_setting(name, value)
+            })
+    }
+}
+
+// Parses a rotation, either as angles in degrees to turn about the x, y and z axes in
+// turn, like `90, 0, 45`, or as an angle about any axis, like `120 about 1, 1, 1`
+fn parse_rotation(s: &str) -> Result<Quaternion, &'static str> {
+    match s.split_once("about") {
+        Some((angle, axis)) => {
+            let angle: f64 = angle
+                .trim()
+                .parse()
+                .ok()
+                .filter(|angle: &f64| angle.is_finite())
+                .ok_or("not a finite number")?;
+            let axis = vector(axis)?;
+            if axis == [0.0; 3] {
+                return Err("the axis can't be all zeros");
+            }
+            Ok(Quaternion::from_axis_angle(axis, angle.to_radians()))
+        }
+        None => Ok(Quaternion::from_euler(vector(s)?.map(f64::to_radians))),
+    }
+}
+
+/// Where an object is, how big it is and which way it's turned at one moment: its points
+/// are scaled, then rotated, then moved to the position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Placement {
+    pub rotation: Quaternion,
+    pub scale: f64,
+    pub position: [f64; 3],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_turn_down_numbers_that_arent_finite() {
+        let object = || Object::surface(Shape::Torus);
+        for (name, value) in [
+            ("position", "0, nan, 0"),
+            ("spin", "inf, 0, 0"),
+            ("orbit", "0, 0, -inf"),
+            ("rotation", "0, NaN, 0"),
+            ("rotation", "inf about 0, 0, 1"),
+            ("swing", "90 about nan, 0, 1"),
+            ("scale", "inf"),
+            ("period", "nan"),
+        ] {
+            assert!(
+                object().with_setting(name, value).is_err(),
+                "{name} = {value}"
+            );
+        }
+        assert_eq!(
+            object().with_setting("position", "1, nan, 0").unwrap_err(),
+            "invalid position `1, nan, 0`: not a finite number"
+        );
+
+        let object = object()
+            .with_setting("position", "1, -2.5, 3")
+            .and_then(|object| object.with_setting("spin", "0, 0, 0.05"))
+            .unwrap();
+        assert_eq!(object.position, [1.0, -2.5, 3.0]);
+        assert_eq!(object.spin, [0.0, 0.0, 0.05]);
+    }
+}
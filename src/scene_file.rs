@@ -0,0 +1,721 @@
+// scene_file.rs
+//
+// Scene files, for setting up everything about what gets drawn and how in one place,
+// rather than in a long line of options.
+
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use donut::{
+    parse::{self, one_of},
+    Light, Material, Object, Parametric, Shape, Torus, MAX_SPEED,
+};
+
+use crate::{cli::Options, load_model};
+
+// One `name = value` line of a scene file
+struct Setting<'a> {
+    line: usize,
+    name: &'a str,
+    value: &'a str,
+}
+
+// An object from the file, and what it's called and belongs to, before it's been put
+// under its parent
+struct Entry<'a> {
+    line: usize,
+    name: Option<&'a str>,
+    parent: Option<(usize, &'a str)>,
+    object: Object,
+}
+
+// A header in square brackets, like `[camera]` or `[material.glaze]`, and the settings
+// under it
+struct Section<'a> {
+    line: usize,
+    header: &'a str,
+    settings: Vec<Setting<'a>>,
+}
+
+/// Reads the scene file at `path` into `options`, adding its objects to any already there
+/// and overriding whatever else it sets.
+///
+/// The file is split into sections, each starting with a header in square brackets and
+/// followed by `name = value` lines. There can be any number of `[object]` and `[light]`
+/// sections, and materials are named in their headers, like `[material.glaze]`, for
+/// objects to pick out with `material = glaze`. Objects can be named, too, for others to
+/// go along with as their `parent`. Anything after a `# ` is a comment, and
+/// values can be put in quotes to keep spaces at either end or a `# ` in them.
+pub fn load(path: &Path, options: &mut Options) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    // Models are found relative to the scene file, not wherever donut was run from
+    read(&text, path.parent().unwrap_or(Path::new("")), options)
+}
+
+// Reads the text of a scene file into `options`, finding any models in `directory`
+fn read(text: &str, directory: &Path, options: &mut Options) -> Result<(), String> {
+    let sections = split(text)?;
+
+    // Objects can use materials from anywhere in the file, before them or after
+    let mut materials = HashMap::new();
+    for section in &sections {
+        if let Some(name) = section.header.strip_prefix("material.") {
+            materials.insert(name.trim(), material(section)?);
+        }
+    }
+
+    let mut lights = Vec::new();
+    let mut entries = Vec::new();
+    for section in &sections {
+        match section.header {
+            "camera" => camera(section, options)?,
+            "light" => lights.push(light(section)?),
+            "object" => entries.push(object(section, &materials, directory)?),
+            "style" => style(section, options)?,
+            "animation" => animation(section, options)?,
+            header if header.starts_with("material.") => {}
+            header => {
+                return Err(at(section.line)(format!(
+                    "unknown section `[{header}]`, expected camera, light, material.<name>, \
+                     object, style or animation"
+                )))
+            }
+        }
+    }
+
+    options.objects.extend(assemble(entries)?);
+
+    // Any lights in the file take the place of the default one, rather than adding to it
+    if !lights.is_empty() {
+        options.lights = lights;
+    }
+    Ok(())
+}
+
+// Splits a scene file into its sections, leaving out blank lines and comments
+fn split(text: &str) -> Result<Vec<Section<'_>>, String> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = strip_comment(header)
+                .trim_end()
+                .strip_suffix(']')
+                .ok_or_else(|| {
+                    at(number)("expected a `]` to end the section header".to_string())
+                })?;
+            sections.push(Section {
+                line: number,
+                header: header.trim(),
+                settings: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(at(number)(format!(
+                "expected `name = value` or a `[section]`, found `{line}`"
+            )));
+        };
+        let Some(section) = sections.last_mut() else {
+            return Err(at(number)(
+                "expected a `[section]` header before any settings".to_string(),
+            ));
+        };
+        section.settings.push(Setting {
+            line: number,
+            name: name.trim(),
+            value: unquote(value.trim()).map_err(at(number))?,
+        });
+    }
+
+    Ok(sections)
+}
+
+// Puts each object under its parent, if it has one, and returns the ones left at the top
+fn assemble(entries: Vec<Entry>) -> Result<Vec<Object>, String> {
+    let mut names = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if let Some(name) = entry.name {
+            if names.insert(name, index).is_some() {
+                return Err(at(entry.line)(format!(
+                    "there's already an object named `{name}`"
+                )));
+            }
+        }
+    }
+
+    let mut parents = Vec::new();
+    for entry in &entries {
+        parents.push(match entry.parent {
+            Some((line, parent)) => Some(
+                *names
+                    .get(parent)
+                    .ok_or_else(|| at(line)(format!("no object named `{parent}`")))?,
+            ),
+            None => None,
+        });
+    }
+
+    let lines: Vec<usize> = entries.iter().map(|entry| entry.line).collect();
+    let mut objects: Vec<Option<Object>> = entries
+        .into_iter()
+        .map(|entry| Some(entry.object))
+        .collect();
+    let top = (0..objects.len())
+        .filter(|&index| parents[index].is_none())
+        .map(|index| adopt(index, &parents, &mut objects))
+        .collect();
+
+    // Anything that didn't end up under one of the objects at the top must have parents
+    // that go round in a loop
+    match objects.iter().position(Option::is_some) {
+        Some(index) => Err(at(lines[index])(
+            "the object is its own parent, or its parent's parent, and so on".to_string(),
+        )),
+        None => Ok(top),
+    }
+}
+
+// Takes the object at `index` out of `objects`, with all its children under it
+fn adopt(index: usize, parents: &[Option<usize>], objects: &mut [Option<Object>]) -> Object {
+    let object = objects[index]
+        .take()
+        .expect("an object should only have one parent");
+    (0..parents.len())
+        .filter(|&child| parents[child] == Some(index))
+        .fold(object, |object, child| {
+            object.with_child(adopt(child, parents, objects))
+        })
+}
+
+// Cuts a comment off the end of a line. Comments start with a `#` on its own, so the ones
+// in colors like `#ff8800` and ramps like `.:-=+*#%@` are left alone.
+fn strip_comment(text: &str) -> &str {
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        let next = text[i + c.len_utf8()..].chars().next();
+        if c == '#' && previous.is_whitespace() && next.is_none_or(char::is_whitespace) {
+            return &text[..i];
+        }
+        previous = c;
+    }
+    text
+}
+
+// Takes the quotes off a value that has them, or the comment off one that doesn't
+fn unquote(value: &str) -> Result<&str, String> {
+    let Some(quote) = value.chars().next().filter(|&c| c == '"' || c == '\'') else {
+        return Ok(strip_comment(value).trim_end());
+    };
+    let Some((inside, rest)) = value[1..].split_once(quote) else {
+        return Err(format!("expected a closing {quote} after {value}"));
+    };
+    if !strip_comment(rest).trim().is_empty() {
+        return Err(format!(
+            "unexpected `{}` after the closing {quote}",
+            rest.trim()
+        ));
+    }
+    Ok(inside)
+}
+
+// Puts the line number in front of an error
+fn at(line: usize) -> impl Fn(String) -> String {
+    move |error| format!("line {line}: {error}")
+}
+
+fn unknown(name: &str, expected: &str) -> String {
+    format!("unknown setting `{name}`, expected {expected}")
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {name} `{value}`"))
+}
+
+// A number greater than zero, for sizes and distances
+fn positive(name: &str, value: &str) -> Result<f64, String> {
+    Some(number(name, value)?)
+        .filter(|n: &f64| n.is_finite() && *n > 0.0)
+        .ok_or_else(|| format!("invalid {name} `{value}`: not a positive number"))
+}
+
+// A number from zero up, for brightnesses
+fn brightness(name: &str, value: &str) -> Result<f64, String> {
+    Some(number(name, value)?)
+        .filter(|n: &f64| n.is_finite() && *n >= 0.0)
+        .ok_or_else(|| format!("invalid {name} `{value}`: not a number from 0 up"))
+}
+
+// A rotation speed, no faster than the fixed-point renderer can turn
+fn speed(name: &str, value: &str) -> Result<f64, String> {
+    Some(number(name, value)?)
+        .filter(|n: &f64| n.abs() <= MAX_SPEED)
+        .ok_or_else(|| {
+            format!("invalid {name} `{value}`: not between -{MAX_SPEED} and {MAX_SPEED}")
+        })
+}
+
+// Three comma-separated numbers, like `0, -1, -1`
+fn vector(name: &str, value: &str) -> Result<[f64; 3], String> {
+    parse::vector(value).map_err(|error| format!("invalid {name} `{value}`: {error}"))
+}
+
+fn camera(section: &Section, options: &mut Options) -> Result<(), String> {
+    for &Setting { line, name, value } in &section.settings {
+        match name {
+            "distance" => options.distance = positive(name, value).map_err(at(line))?,
+            "zoom" => options.zoom = positive(name, value).map_err(at(line))?,
+            _ => return Err(at(line)(unknown(name, "distance or zoom"))),
+        }
+    }
+    Ok(())
+}
+
+fn light(section: &Section) -> Result<Light, String> {
+    let mut light = Light::default();
+    for &Setting { line, name, value } in &section.settings {
+        match name {
+            "direction" => {
+                light.direction = vector(name, value)
+                    .and_then(|direction| match direction {
+                        [0.0, 0.0, 0.0] => Err("a light needs a direction".to_string()),
+                        _ => Ok(direction),
+                    })
+                    .map_err(at(line))?
+            }
+            "intensity" => light.intensity = brightness(name, value).map_err(at(line))?,
+            _ => return Err(at(line)(unknown(name, "direction or intensity"))),
+        }
+    }
+    Ok(light)
+}
+
+fn material(section: &Section) -> Result<Material, String> {
+    let mut material = Material::default();
+    for &Setting { line, name, value } in &section.settings {
+        match name {
+            "color" => material.color = Some(value.parse().map_err(at(line))?),
+            "ambient" => material.ambient = brightness(name, value).map_err(at(line))?,
+            "diffuse" => material.diffuse = brightness(name, value).map_err(at(line))?,
+            _ => return Err(at(line)(unknown(name, "color, ambient or diffuse"))),
+        }
+    }
+    Ok(material)
+}
+
+fn object<'a>(
+    section: &Section<'a>,
+    materials: &HashMap<&str, Material>,
+    directory: &Path,
+) -> Result<Entry<'a>, String> {
+    const SOURCES: [&str; 5] = ["shape", "solid", "model", "surface", "implicit"];
+    // What an object in a scene file takes on top of a source and the settings any object
+    // takes
+    const OWN: [&str; 6] = [
+        "minor_radius",
+        "major_radius",
+        "smooth",
+        "material",
+        "name",
+        "parent",
+    ];
+
+    let mut source = None;
+    let mut torus = Torus::default();
+    let mut radii = None;
+    let mut smooth = false;
+    let mut material = None;
+    let mut settings = Vec::new();
+    let mut own_name = None;
+    let mut parent = None;
+
+    for setting @ &Setting { line, name, value } in &section.settings {
+        match name {
+            _ if SOURCES.contains(&name) && source.is_some() => {
+                return Err(at(line)(
+                    "an object can only have one shape, solid, model, surface or implicit"
+                        .to_string(),
+                ))
+            }
+            _ if SOURCES.contains(&name) => source = Some(setting),
+            "minor_radius" => {
+                torus.minor_radius = positive(name, value).map_err(at(line))?;
+                radii = Some(line);
+            }
+            "major_radius" => {
+                torus.major_radius = positive(name, value).map_err(at(line))?;
+                radii = Some(line);
+            }
+            "smooth" => smooth = number(name, value).map_err(at(line))?,
+            "material" => {
+                let found = materials.get(value).copied();
+                material = Some(found.ok_or_else(|| {
+                    at(line)(format!("no `[material.{value}]` section to go by"))
+                })?);
+            }
+            "name" => own_name = Some(value),
+            "parent" => parent = Some((line, value)),
+            _ if Object::SETTINGS.contains(&name) => settings.push(setting),
+            _ => {
+                let expected: Vec<_> = [&SOURCES[..], &OWN, &Object::SETTINGS].concat();
+                return Err(at(line)(unknown(name, &one_of(&expected))));
+            }
+        }
+    }
+
+    let Some(&Setting { line, name, value }) = source else {
+        return Err(at(section.line)(
+            "an object needs a shape, solid, model, surface or implicit".to_string(),
+        ));
+    };
+    let shape = match name {
+        "shape" => Some(value.parse::<Shape>().map_err(at(line))?),
+        _ => None,
+    };
+    if let Some(radius_line) = radii.filter(|_| shape != Some(Shape::Torus)) {
+        return Err(at(radius_line)(
+            "only a `shape = torus` can have a minor_radius or major_radius".to_string(),
+        ));
+    }
+
+    let object = match (name, shape) {
+        (_, Some(Shape::Torus)) => Object::surface(torus),
+        (_, Some(shape)) => Object::surface(shape),
+        ("solid", _) => Object::solid(value.parse().map_err(at(line))?),
+        ("model", _) => load_model(&directory.join(value), smooth)
+            .map_err(|error| at(line)(format!("couldn't load {value}: {error}")))?,
+        ("surface", _) => Object::surface(value.parse::<Parametric>().map_err(at(line))?.fitted()),
+        _ => Object::implicit(value.parse().map_err(at(line))?),
+    };
+    let object = match material {
+        Some(material) => object.with_material(material),
+        None => object,
+    };
+
+    // Settings go on top of the material, whichever order they're written in
+    let object = settings
+        .into_iter()
+        .try_fold(object, |object, &Setting { line, name, value }| {
+            object.with_setting(name, value).map_err(at(line))
+        })?;
+    Ok(Entry {
+        line: section.line,
+        name: own_name,
+        parent,
+        object,
+    })
+}
+
+fn style(section: &Section, options: &mut Options) -> Result<(), String> {
+    for &Setting { line, name, value } in &section.settings {
+        match name {
+            "mode" => options.mode = value.parse().map_err(at(line))?,
+            "ramp" => options.ramp = value.parse().map_err(at(line))?,
+            "color" => options.color = value.parse().map_err(at(line))?,
+            "gradient" => options.gradient = value.parse().map_err(at(line))?,
+            "depth_shading" => options.depth_shading = number(name, value).map_err(at(line))?,
+            _ => {
+                return Err(at(line)(unknown(
+                    name,
+                    "mode, ramp, color, gradient or depth_shading",
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn animation(section: &Section, options: &mut Options) -> Result<(), String> {
+    for &Setting { line, name, value } in &section.settings {
+        match name {
+            "fps" => {
+                let fps = positive(name, value).map_err(at(line))?;
+                options.delay = Duration::try_from_secs_f64(1.0 / fps)
+                    .map_err(|_| at(line)(format!("invalid fps `{value}`: too slow")))?;
+            }
+            "delay" => {
+                options.delay = Duration::from_millis(number(name, value).map_err(at(line))?)
+            }
+            "frames" => options.frames = Some(number(name, value).map_err(at(line))?),
+            "duration" => {
+                let seconds = positive(name, value).map_err(at(line))?;
+                options.duration = Some(
+                    Duration::try_from_secs_f64(seconds)
+                        .map_err(|_| at(line)(format!("invalid duration `{value}`")))?,
+                );
+            }
+            "speed_a" => options.speed_a = speed(name, value).map_err(at(line))?,
+            "speed_b" => options.speed_b = speed(name, value).map_err(at(line))?,
+            // This stops angles A and B turning, unless they're set again after it
+            "spin" => {
+                options.spin = vector(name, value).map_err(at(line))?;
+                options.speed_a = 0.0;
+                options.speed_b = 0.0;
+            }
+            _ => {
+                return Err(at(line)(unknown(
+                    name,
+                    "fps, delay, frames, duration, speed_a, speed_b or spin",
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use donut::{ColorMode, Mode, Ramp};
+
+    fn read_scene(text: &str) -> Result<Options, String> {
+        let mut options = Options::default();
+        read(text, Path::new(""), &mut options)?;
+        Ok(options)
+    }
+
+    fn error(text: &str) -> String {
+        read_scene(text).unwrap_err()
+    }
+
+    #[test]
+    fn sections_and_settings() {
+        let options = read_scene(
+            "# a scene\n\
+             [camera]\n\
+             distance = 8   # further away\n\
+             zoom = 1.5\n\
+             \n\
+             [ style ]\n\
+             mode = braille\n\
+             ramp = \" .:-=+*#%@\"\n\
+             color = truecolor\n\
+             depth_shading = true\n\
+             \n\
+             [animation]\n\
+             fps = 20\n\
+             frames = 100\n\
+             speed_a = 0.02\n\
+             spin = 0, 0, 0.1\n\
+             speed_b = -0.01\n",
+        )
+        .unwrap();
+        assert_eq!(options.distance, 8.0);
+        assert_eq!(options.zoom, 1.5);
+        assert_eq!(options.mode, Mode::Braille);
+        assert_eq!(options.ramp, " .:-=+*#%@".parse::<Ramp>().unwrap());
+        assert_eq!(options.color, ColorMode::TrueColor);
+        assert!(options.depth_shading);
+        assert_eq!(options.delay, Duration::from_millis(50));
+        assert_eq!(options.frames, Some(100));
+        // The spin stops angle A turning, but angle B is set again after it
+        assert_eq!(options.spin, [0.0, 0.0, 0.1]);
+        assert_eq!((options.speed_a, options.speed_b), (0.0, -0.01));
+        assert!(options.objects.is_empty());
+    }
+
+    #[test]
+    fn quotes_and_comments() {
+        let options = read_scene("[style]\nramp = ' .#' # with a space first\n").unwrap();
+        assert_eq!(options.ramp, " .#".parse::<Ramp>().unwrap());
+        // A `#` that isn't on its own doesn't start a comment
+        let options = read_scene("[style]\nramp = .:#@\n").unwrap();
+        assert_eq!(options.ramp, ".:#@".parse::<Ramp>().unwrap());
+
+        assert_eq!(
+            error("[style]\nramp = ' .#\n"),
+            "line 2: expected a closing ' after ' .#"
+        );
+        assert_eq!(
+            error("[style]\nramp = \" .\" x\n"),
+            "line 2: unexpected `x` after the closing \""
+        );
+    }
+
+    #[test]
+    fn lights() {
+        let options = read_scene(
+            "[light]\ndirection = 1, 0, 0\nintensity = 0.5\n\n[light]\ndirection = 0, 1, 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options.lights,
+            [
+                Light {
+                    direction: [1.0, 0.0, 0.0],
+                    intensity: 0.5,
+                },
+                Light {
+                    direction: [0.0, 1.0, 0.0],
+                    ..Light::default()
+                },
+            ]
+        );
+
+        // Without any lights, the default one stays
+        let options = read_scene("[camera]\n").unwrap();
+        assert_eq!(options.lights, [Light::default()]);
+
+        assert_eq!(
+            error("[light]\ndirection = 0, 0, 0\n"),
+            "line 2: a light needs a direction"
+        );
+        assert_eq!(
+            error("[light]\ndirection = 0, 1\n"),
+            "line 2: invalid direction `0, 1`: expected three numbers, for x, y and z"
+        );
+    }
+
+    #[test]
+    fn objects() {
+        let options = read_scene(
+            "[material.glaze]\ncolor = #ff88cc\nambient = 0.1\n\n\
+             [object]\nname = sun\nshape = sphere\nmaterial = glaze\n\n\
+             [object]\nname = moon\nparent = planet\nsolid = sphere(1)\nscale = 0.1\n\n\
+             [object]\nname = planet\nparent = sun\nshape = torus\nminor_radius = 0.5\n\
+             position = 2, 0, 0\norbit = 0, 0, 0.05\n\n\
+             [object]\nsurface = \"x = u; y = v; z = u v\"\nswing = 0, 90, 0\nperiod = 20\n",
+        )
+        .unwrap();
+
+        // The sun and the surface are at the top, with the moon under the planet under
+        // the sun, wherever they were in the file
+        assert_eq!(options.objects.len(), 2);
+        let planets = options.objects[0].children();
+        assert_eq!(planets.len(), 1);
+        assert_eq!(planets[0].children().len(), 1);
+        assert!(planets[0].children()[0].children().is_empty());
+        assert!(options.objects[1].children().is_empty());
+    }
+
+    #[test]
+    fn object_errors() {
+        assert_eq!(
+            error("[object]\nshape = torus\nwobble = 1\n"),
+            "line 3: unknown setting `wobble`, expected shape, solid, model, surface, \
+             implicit, minor_radius, major_radius, smooth, material, name, parent, position, \
+             scale, rotation, spin, orbit, swing, period, color, ambient or diffuse"
+        );
+        assert_eq!(
+            error("[object]\nscale = 2\n"),
+            "line 1: an object needs a shape, solid, model, surface or implicit"
+        );
+        assert_eq!(
+            error("[object]\nshape = torus\nsolid = sphere(1)\n"),
+            "line 3: an object can only have one shape, solid, model, surface or implicit"
+        );
+        assert_eq!(
+            error("[object]\nshape = sphere\nminor_radius = 1\n"),
+            "line 3: only a `shape = torus` can have a minor_radius or major_radius"
+        );
+        assert_eq!(
+            error("[object]\nshape = torus\nmaterial = glaze\n"),
+            "line 3: no `[material.glaze]` section to go by"
+        );
+        assert_eq!(
+            error("[object]\n\nshape = torus\nscale = big\n")
+                .split(':')
+                .next(),
+            Some("line 4")
+        );
+        assert_eq!(
+            error("[object]\nshape = torus\nparent = sun\n"),
+            "line 3: no object named `sun`"
+        );
+        assert_eq!(
+            error("[object]\nname = a\nshape = torus\n[object]\nname = a\nshape = sphere\n"),
+            "line 4: there's already an object named `a`"
+        );
+        assert_eq!(
+            error(
+                "[object]\nname = a\nparent = b\nshape = torus\n\
+                 [object]\nname = b\nparent = a\nshape = sphere\n"
+            ),
+            "line 1: the object is its own parent, or its parent's parent, and so on"
+        );
+    }
+
+    #[test]
+    fn unknown_settings_and_sections() {
+        assert_eq!(
+            error("[camera]\nheight = 2\n"),
+            "line 2: unknown setting `height`, expected distance or zoom"
+        );
+        assert_eq!(
+            error("[light]\ncolor = #fff\n"),
+            "line 2: unknown setting `color`, expected direction or intensity"
+        );
+        assert_eq!(
+            error("[material.glaze]\nshine = 1\n"),
+            "line 2: unknown setting `shine`, expected color, ambient or diffuse"
+        );
+        assert_eq!(
+            error("[style]\nfont = mono\n"),
+            "line 2: unknown setting `font`, expected mode, ramp, color, gradient or \
+             depth_shading"
+        );
+        assert_eq!(
+            error("[animation]\nloop = true\n"),
+            "line 2: unknown setting `loop`, expected fps, delay, frames, duration, speed_a, \
+             speed_b or spin"
+        );
+        assert_eq!(
+            error("# nothing yet\n[sky]\n"),
+            "line 2: unknown section `[sky]`, expected camera, light, material.<name>, \
+             object, style or animation"
+        );
+    }
+
+    #[test]
+    fn bad_lines_and_values() {
+        assert_eq!(
+            error("distance = 2\n"),
+            "line 1: expected a `[section]` header before any settings"
+        );
+        assert_eq!(
+            error("[camera\n"),
+            "line 1: expected a `]` to end the section header"
+        );
+        assert_eq!(
+            error("[camera]\n\ndistance\n"),
+            "line 3: expected `name = value` or a `[section]`, found `distance`"
+        );
+        assert_eq!(
+            error("[camera]\ndistance = -2\n"),
+            "line 2: invalid distance `-2`: not a positive number"
+        );
+        assert_eq!(
+            error("[light]\nintensity = nan\n"),
+            "line 2: invalid intensity `nan`: not a number from 0 up"
+        );
+        assert_eq!(
+            error("[animation]\nframes = 1.5\n"),
+            "line 2: invalid frames `1.5`"
+        );
+        assert_eq!(
+            error("[animation]\nfps = nan\n"),
+            "line 2: invalid fps `nan`: not a positive number"
+        );
+        assert_eq!(
+            error("[animation]\nfps = 1e-30\n"),
+            "line 2: invalid fps `1e-30`: too slow"
+        );
+        assert_eq!(
+            error("[animation]\nspeed_a = 2\n"),
+            "line 2: invalid speed_a `2`: not between -0.25 and 0.25"
+        );
+        assert_eq!(
+            error("[style]\nmode = ascii\n").split(':').next(),
+            Some("line 2")
+        );
+    }
+}
@@ -0,0 +1,694 @@
+// sdf.rs
+//
+// Solids described by signed distance functions, built up from simple shapes with
+// constructive solid geometry, and a raymarcher to draw them with one ray per pixel.
+
+use std::str::FromStr;
+
+use crate::{
+    float::{Camera, Real},
+    frame::Frame,
+    implicit::enter_bounds,
+    mesh::{length, normalize},
+    quaternion::Quaternion,
+    shapes::Shape,
+};
+
+// How many steps a ray gets to find the surface before giving up
+const MAX_STEPS: u32 = 160;
+// How close a ray has to get to the surface to count as hitting it
+const HIT_DISTANCE: f64 = 1e-4;
+// The step used to work out normals from how the distance changes
+const NORMAL_STEP: f64 = 1e-4;
+
+/// A solid shape, described by how far any point is from its surface.
+///
+/// Raymarching a solid fills every pixel it covers, however big the frame gets, rather than
+/// leaving holes where samples of a surface spread too thin. Solids can be moved, turned
+/// and combined with each other to build up more complicated ones, either with the
+/// methods below or by parsing them from text like
+/// `subtract(torus, move(0, -2, 1, sphere(1)))`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Solid {
+    /// A donut around the z-axis, like [`Torus`](crate::Torus).
+    Torus {
+        minor_radius: f64,
+        major_radius: f64,
+    },
+    /// A sphere around the origin.
+    Sphere { radius: f64 },
+    /// A box around the origin, lined up with the axes.
+    Box {
+        /// Half the size of the box along each axis.
+        half_size: [f64; 3],
+    },
+    /// A cylinder around the z-axis, closed at both ends.
+    Cylinder { radius: f64, half_height: f64 },
+    /// A cone around the z-axis, with its base below the origin and its tip above, like
+    /// [`Cone`](crate::Cone).
+    Cone { radius: f64, height: f64 },
+    /// Another solid, moved by `offset`.
+    Translated { offset: [f64; 3], solid: Box<Solid> },
+    /// Another solid, turned by `rotation`.
+    Rotated {
+        rotation: Quaternion,
+        solid: Box<Solid>,
+    },
+    /// Everything that's inside any of the solids.
+    Union(Vec<Solid>),
+    /// Everything that's inside all of the solids.
+    Intersection(Vec<Solid>),
+    /// Everything that's inside `solid` but not `cutout`.
+    Difference {
+        solid: Box<Solid>,
+        cutout: Box<Solid>,
+    },
+    /// Everything that's inside any of the solids, with the creases where they meet
+    /// filled in.
+    Blend {
+        /// Roughly how far from where they meet the solids start to melt together.
+        smoothness: f64,
+        solids: Vec<Solid>,
+    },
+}
+
+impl Solid {
+    /// Moves the solid by `offset`.
+    pub fn translated(self, offset: [f64; 3]) -> Self {
+        Solid::Translated {
+            offset,
+            solid: Box::new(self),
+        }
+    }
+
+    /// Turns the solid by `rotation`.
+    pub fn rotated(self, rotation: Quaternion) -> Self {
+        Solid::Rotated {
+            rotation,
+            solid: Box::new(self),
+        }
+    }
+
+    /// Combines the solid with another, keeping everything that's inside either.
+    pub fn union(self, other: Solid) -> Self {
+        Solid::Union(vec![self, other])
+    }
+
+    /// Keeps only what's inside both the solid and `other`.
+    pub fn intersect(self, other: Solid) -> Self {
+        Solid::Intersection(vec![self, other])
+    }
+
+    /// Cuts `cutout` out of the solid.
+    pub fn subtract(self, cutout: Solid) -> Self {
+        Solid::Difference {
+            solid: Box::new(self),
+            cutout: Box::new(cutout),
+        }
+    }
+
+    /// Combines the solid with another like [`union`](Solid::union), but melts them
+    /// together wherever they're within about `smoothness` of each other.
+    pub fn blend(self, other: Solid, smoothness: f64) -> Self {
+        Solid::Blend {
+            smoothness,
+            solids: vec![self, other],
+        }
+    }
+
+    /// How far `point` is from the surface of the solid: positive outside, negative inside.
+    ///
+    /// This can underestimate the distance, but never overestimates it, so it's always
+    /// safe to step that far along a ray.
+    pub fn distance(&self, point: [f64; 3]) -> f64 {
+        let [x, y, z] = point;
+        match self {
+            Solid::Torus {
+                minor_radius,
+                major_radius,
+            } => (x.hypot(y) - major_radius).hypot(z) - minor_radius,
+            Solid::Sphere { radius } => length(point) - radius,
+            Solid::Box { half_size } => {
+                let outside = [0, 1, 2].map(|axis| point[axis].abs() - half_size[axis]);
+                let inside = outside[0].max(outside[1]).max(outside[2]).min(0.0);
+                length(outside.map(|n| n.max(0.0))) + inside
+            }
+            Solid::Cylinder {
+                radius,
+                half_height,
+            } => {
+                let outside = [x.hypot(y) - radius, z.abs() - half_height];
+                outside[0].max(outside[1]).min(0.0) + outside[0].max(0.0).hypot(outside[1].max(0.0))
+            }
+            Solid::Cone { radius, height } => {
+                // Measured from halfway up, the same distance from the base as the surface
+                // version of the cone
+                let half_height = height / 2.0;
+                let (across, up) = (x.hypot(y), z - (-0.43 * height + half_height));
+
+                // The nearest points on the base and on the sloping side
+                let base = [
+                    across - across.min(if up < 0.0 { *radius } else { 0.0 }),
+                    up.abs() - half_height,
+                ];
+                let slope = [-radius, 2.0 * half_height];
+                let t = ((-across) * slope[0] + (half_height - up) * slope[1])
+                    / (slope[0] * slope[0] + slope[1] * slope[1]);
+                let side = [
+                    across + slope[0] * t.clamp(0.0, 1.0),
+                    up - half_height + slope[1] * t.clamp(0.0, 1.0),
+                ];
+
+                let inside = side[0] < 0.0 && base[1] < 0.0;
+                let distance = base[0].hypot(base[1]).min(side[0].hypot(side[1]));
+                if inside {
+                    -distance
+                } else {
+                    distance
+                }
+            }
+            Solid::Translated { offset, solid } => {
+                solid.distance([0, 1, 2].map(|axis| point[axis] - offset[axis]))
+            }
+            // Turning the point back the other way puts it where it was before the turn
+            Solid::Rotated { rotation, solid } => solid.distance(rotation.inverse().rotate(point)),
+            Solid::Union(solids) => solids
+                .iter()
+                .map(|solid| solid.distance(point))
+                .fold(f64::INFINITY, f64::min),
+            Solid::Intersection(solids) => solids
+                .iter()
+                .map(|solid| solid.distance(point))
+                .fold(f64::NEG_INFINITY, f64::max),
+            Solid::Difference { solid, cutout } => {
+                solid.distance(point).max(-cutout.distance(point))
+            }
+            Solid::Blend { smoothness, solids } => solids
+                .iter()
+                .map(|solid| solid.distance(point))
+                .reduce(|a, b| smooth_min(a, b, *smoothness))
+                .unwrap_or(f64::INFINITY),
+        }
+    }
+
+    // The direction the surface faces at `point`, from how the distance changes around it
+    fn normal(&self, point: [f64; 3]) -> [f64; 3] {
+        normalize([0, 1, 2].map(|axis| {
+            let (mut ahead, mut behind) = (point, point);
+            ahead[axis] += NORMAL_STEP;
+            behind[axis] -= NORMAL_STEP;
+            self.distance(ahead) - self.distance(behind)
+        }))
+    }
+
+    // The radius of a sphere around the origin that the whole solid fits inside
+    fn bounding_radius(&self) -> f64 {
+        match self {
+            Solid::Torus {
+                minor_radius,
+                major_radius,
+            } => minor_radius + major_radius,
+            Solid::Sphere { radius } => *radius,
+            Solid::Box { half_size } => length(*half_size),
+            Solid::Cylinder {
+                radius,
+                half_height,
+            } => radius.hypot(*half_height),
+            // The base is a little less than halfway down, and the tip a little more than
+            // halfway up
+            Solid::Cone { radius, height } => radius.hypot(0.57 * height),
+            Solid::Translated { offset, solid } => length(*offset) + solid.bounding_radius(),
+            Solid::Rotated { solid, .. } => solid.bounding_radius(),
+            Solid::Union(solids) => solids
+                .iter()
+                .map(Solid::bounding_radius)
+                .fold(0.0, f64::max),
+            Solid::Intersection(solids) => solids
+                .iter()
+                .map(Solid::bounding_radius)
+                .fold(f64::INFINITY, f64::min),
+            Solid::Difference { solid, .. } => solid.bounding_radius(),
+            // Melting the solids together fills them out by up to a quarter of the smoothness
+            Solid::Blend { smoothness, solids } => {
+                solids
+                    .iter()
+                    .map(Solid::bounding_radius)
+                    .fold(0.0, f64::max)
+                    + smoothness.max(0.0) / 4.0
+            }
+        }
+    }
+
+    /// Casts a ray through every pixel of `frame`, and draws wherever it hits the solid.
+    pub(crate) fn march<T: Real>(&self, camera: &Camera<T>, frame: &mut Frame) {
+        // Rays only need following for as long as they're within reach of the solid, which
+        // depends on how big it is and how far away the camera is, not on any fixed distance
+        let radius = self.bounding_radius() + HIT_DISTANCE;
+
+        for y in 0..frame.height() {
+            for x in 0..frame.width() {
+                let (origin, direction) = camera.ray(x, y);
+                let origin = origin.map(T::to_f64);
+                let direction = normalize(direction.map(T::to_f64));
+
+                let Some((near, far)) = enter_bounds(origin, direction, radius) else {
+                    continue;
+                };
+                let mut travelled = near;
+                for _ in 0..MAX_STEPS {
+                    let point = [0, 1, 2].map(|axis| origin[axis] + direction[axis] * travelled);
+                    let distance = self.distance(point);
+
+                    if distance < HIT_DISTANCE {
+                        let normal = camera.rotate(self.normal(point).map(T::from_f64));
+                        let depth = camera.view(point)[2];
+                        camera.plot(
+                            frame,
+                            x,
+                            y,
+                            camera.luminance(normal).to_f64(),
+                            depth.to_f64(),
+                        );
+                        break;
+                    }
+
+                    travelled += distance;
+                    if travelled > far {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Solid {
+    type Err = String;
+
+    /// Parses a solid written out like a function call, such as
+    /// `blend(0.5, torus, move(0, 0, 1.5, sphere(1)))`. The shapes are:
+    ///
+    /// - `torus(minor_radius, major_radius)`
+    /// - `sphere(radius)`
+    /// - `box(half_x, half_y, half_z)` or `cube(half_size)`
+    /// - `cylinder(radius, half_height)`
+    /// - `cone(radius, height)`
+    ///
+    /// or any of the built-in [`Shape`]s that are solid by name, like `torus` or `cube`,
+    /// at their usual size. They can be moved and turned with `move(x, y, z, solid)` and
+    /// `rotate(x, y, z, solid)`, which takes angles in degrees, and combined with
+    /// `union(solids...)`, `intersect(solids...)`, `subtract(solid, cutouts...)` and
+    /// `blend(smoothness, solids...)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { rest: s };
+        let solid = parser.solid()?;
+        match parser.peek() {
+            None => Ok(solid),
+            Some(c) => Err(format!("unexpected `{c}` after the end of the solid")),
+        }
+    }
+}
+
+// Reads a solid from text, one piece at a time from the front
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+// Something passed to a shape or an operation: either a number or another solid
+enum Argument {
+    Number(f64),
+    Solid(Solid),
+}
+
+impl Parser<'_> {
+    // The next character that isn't whitespace, without taking it
+    fn peek(&mut self) -> Option<char> {
+        self.rest = self.rest.trim_start();
+        self.rest.chars().next()
+    }
+
+    // Takes the next character if it's `c`
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.rest = &self.rest[c.len_utf8()..];
+        }
+        found
+    }
+
+    // Takes everything up to the next character that `keep` turns down
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> &str {
+        self.peek();
+        let end = self.rest.find(|c| !keep(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn solid(&mut self) -> Result<Solid, String> {
+        let name = self
+            .take_while(|c| c.is_alphanumeric() || c == '_')
+            .to_lowercase();
+        if name.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => format!("expected a solid, found `{c}`"),
+                None => "expected a solid".to_string(),
+            });
+        }
+
+        let mut numbers = Vec::new();
+        let mut solids = Vec::new();
+        if self.eat('(') && !self.eat(')') {
+            loop {
+                match self.argument()? {
+                    Argument::Number(_) if !solids.is_empty() => {
+                        return Err(format!("the numbers for `{name}` go before any solids"))
+                    }
+                    Argument::Number(number) => numbers.push(number),
+                    Argument::Solid(solid) => solids.push(solid),
+                }
+
+                if self.eat(')') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return Err(format!("expected `,` or `)` in the arguments to `{name}`"));
+                }
+            }
+        }
+
+        build(&name, &numbers, solids)
+    }
+
+    fn argument(&mut self) -> Result<Argument, String> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                let number =
+                    self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'));
+                number
+                    .parse()
+                    .ok()
+                    .filter(|number: &f64| number.is_finite())
+                    .map(Argument::Number)
+                    .ok_or_else(|| format!("invalid number `{number}`"))
+            }
+            _ => self.solid().map(Argument::Solid),
+        }
+    }
+}
+
+// Puts together the solid called `name` from the arguments it was given
+fn build(name: &str, numbers: &[f64], mut solids: Vec<Solid>) -> Result<Solid, String> {
+    let solid = match (name, numbers, solids.len()) {
+        ("torus", &[minor_radius, major_radius], 0) => Solid::Torus {
+            minor_radius: size(name, "minor_radius", minor_radius)?,
+            major_radius: size(name, "major_radius", major_radius)?,
+        },
+        ("sphere", &[radius], 0) => Solid::Sphere {
+            radius: size(name, "radius", radius)?,
+        },
+        ("box", &[x, y, z], 0) => Solid::Box {
+            half_size: [
+                size(name, "half_x", x)?,
+                size(name, "half_y", y)?,
+                size(name, "half_z", z)?,
+            ],
+        },
+        ("box" | "cube", &[half_size], 0) => Solid::Box {
+            half_size: [size(name, "half_size", half_size)?; 3],
+        },
+        ("cylinder", &[radius, half_height], 0) => Solid::Cylinder {
+            radius: size(name, "radius", radius)?,
+            half_height: size(name, "half_height", half_height)?,
+        },
+        ("cone", &[radius, height], 0) => Solid::Cone {
+            radius: size(name, "radius", radius)?,
+            height: size(name, "height", height)?,
+        },
+        ("move", &[x, y, z], 1) => solids.remove(0).translated([x, y, z]),
+        ("rotate", &[x, y, z], 1) => solids
+            .remove(0)
+            .rotated(Quaternion::from_euler([x, y, z].map(f64::to_radians))),
+        ("union", &[], 1..) => Solid::Union(solids),
+        ("intersect", &[], 1..) => Solid::Intersection(solids),
+        ("subtract", &[], 2..) => {
+            let solid = solids.remove(0);
+            // Cutting out several solids is the same as cutting out all of them at once
+            let cutout = if solids.len() == 1 {
+                solids.remove(0)
+            } else {
+                Solid::Union(solids)
+            };
+            solid.subtract(cutout)
+        }
+        ("blend", &[smoothness], 1..) => Solid::Blend { smoothness, solids },
+        (_, &[], 0) => match name.parse::<Shape>() {
+            Ok(shape) => shape
+                .solid()
+                .ok_or_else(|| format!("`{name}` isn't solid, so it can't be raymarched"))?,
+            Err(_) => return Err(usage(name)),
+        },
+        _ => return Err(usage(name)),
+    };
+    Ok(solid)
+}
+
+// Checks a radius or other size given to the shape `name`, which can't be turned inside
+// out or flattened away to nothing
+fn size(name: &str, argument: &str, value: f64) -> Result<f64, String> {
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!(
+            "the {argument} of `{name}` must be greater than zero, not {value}"
+        ))
+    }
+}
+
+// What went wrong with the arguments to `name`
+fn usage(name: &str) -> String {
+    let expected = match name {
+        "torus" => "torus(minor_radius, major_radius)",
+        "sphere" => "sphere(radius)",
+        "box" => "box(half_x, half_y, half_z)",
+        "cube" => "cube(half_size)",
+        "cylinder" => "cylinder(radius, half_height)",
+        "cone" => "cone(radius, height)",
+        "move" => "move(x, y, z, solid)",
+        "rotate" => "rotate(x_degrees, y_degrees, z_degrees, solid)",
+        "union" => "union(solids...)",
+        "intersect" => "intersect(solids...)",
+        "subtract" => "subtract(solid, cutouts...)",
+        "blend" => "blend(smoothness, solids...)",
+        _ => {
+            return format!(
+                "unknown solid `{name}`, expected torus, sphere, box, cube, cylinder, cone, \
+                 move, rotate, union, intersect, subtract or blend"
+            )
+        }
+    };
+    format!("wrong arguments to `{name}`, expected {expected}")
+}
+
+// The smaller of two distances, but rounded off where they're within `smoothness` of each
+// other so the solids they come from melt together
+fn smooth_min(a: f64, b: f64, smoothness: f64) -> f64 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (smoothness - (a - b).abs()).max(0.0) / smoothness;
+    a.min(b) - h * h * smoothness / 4.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(text: &str) -> Solid {
+        text.parse()
+            .unwrap_or_else(|error| panic!("couldn't parse `{text}`: {error}"))
+    }
+
+    fn assert_distance(solid: &Solid, point: [f64; 3], expected: f64) {
+        let distance = solid.distance(point);
+        assert!(
+            (distance - expected).abs() < 1e-9,
+            "{solid:?} at {point:?}: expected {expected}, found {distance}"
+        );
+    }
+
+    #[test]
+    fn shapes() {
+        let sphere = solid("sphere(1)");
+        assert_distance(&sphere, [3.0, 0.0, 0.0], 2.0);
+        assert_distance(&sphere, [0.0, 0.0, 0.0], -1.0);
+
+        let torus = solid("torus(1, 2)");
+        assert_distance(&torus, [3.0, 0.0, 0.0], 0.0);
+        assert_distance(&torus, [0.0, 0.0, 0.0], 1.0);
+
+        let cuboid = solid("box(1, 2, 3)");
+        assert_distance(&cuboid, [2.0, 0.0, 0.0], 1.0);
+        assert_distance(&cuboid, [0.0, 0.0, 0.0], -1.0);
+        assert_eq!(solid("cube(1)"), solid("box(1, 1, 1)"));
+
+        let cylinder = solid("cylinder(1, 2)");
+        assert_distance(&cylinder, [0.0, 0.0, 3.0], 1.0);
+        assert_distance(&cylinder, [3.0, 0.0, 0.0], 2.0);
+    }
+
+    #[test]
+    fn move_and_rotate() {
+        assert_distance(&solid("move(0, 0, 2, sphere(1))"), [0.0, 0.0, 2.0], -1.0);
+
+        // A quarter turn about z takes the x-axis round to the y-axis
+        let turned = solid("rotate(0, 0, 90, move(2, 0, 0, sphere(0.5)))");
+        assert_distance(&turned, [0.0, 2.0, 0.0], -0.5);
+        assert_distance(&turned, [2.0, 0.0, 0.0], 2.0_f64.hypot(2.0) - 0.5);
+
+        // The turn about x comes first, which leaves the x-axis alone for the turn about y
+        // to take round to -z
+        let turned = solid("rotate(90, 90, 0, move(2, 0, 0, sphere(0.5)))");
+        assert_distance(&turned, [0.0, 0.0, -2.0], -0.5);
+    }
+
+    #[test]
+    fn union() {
+        let union = solid("union(sphere(1), move(3, 0, 0, sphere(1)))");
+        assert_distance(&union, [1.5, 0.0, 0.0], 0.5);
+        assert_distance(&union, [3.0, 0.0, 0.0], -1.0);
+        assert_distance(&union, [-2.0, 0.0, 0.0], 1.0);
+    }
+
+    #[test]
+    fn intersect() {
+        let lens = solid("intersect(sphere(2), move(2, 0, 0, sphere(2)))");
+        assert_distance(&lens, [1.0, 0.0, 0.0], -1.0);
+        assert_distance(&lens, [0.0, 0.0, 0.0], 0.0);
+        assert_distance(&lens, [-1.0, 0.0, 0.0], 1.0);
+    }
+
+    #[test]
+    fn subtract() {
+        let shell = solid("subtract(sphere(2), sphere(1))");
+        assert_distance(&shell, [0.0, 0.0, 0.0], 1.0);
+        assert_distance(&shell, [1.5, 0.0, 0.0], -0.5);
+        assert_distance(&shell, [3.0, 0.0, 0.0], 1.0);
+
+        // Several cutouts all get cut out
+        let holes = solid("subtract(cube(2), move(2, 0, 0, sphere(1)), move(-2, 0, 0, sphere(1)))");
+        assert_distance(&holes, [1.5, 0.0, 0.0], 0.5);
+        assert_distance(&holes, [-1.5, 0.0, 0.0], 0.5);
+        assert_distance(&holes, [0.0, 0.0, 0.0], -1.0);
+    }
+
+    #[test]
+    fn blend() {
+        let blend = solid("blend(1, sphere(1), move(3, 0, 0, sphere(1)))");
+        // Halfway between, where both are 0.5 away, they melt together by a quarter of the
+        // smoothness
+        assert_distance(&blend, [1.5, 0.0, 0.0], 0.25);
+        // Far enough from where they meet, it's the same as a union
+        assert_distance(&blend, [-3.0, 0.0, 0.0], 2.0);
+
+        // No smoothness at all is just a union
+        let union = solid("union(sphere(1), move(3, 0, 0, sphere(1)))");
+        let sharp = solid("blend(0, sphere(1), move(3, 0, 0, sphere(1)))");
+        assert_distance(&sharp, [1.5, 0.0, 0.0], union.distance([1.5, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            solid(" UNION ( sphere(1) , Cube( .5 ) ) "),
+            Solid::Sphere { radius: 1.0 }.union(Solid::Box {
+                half_size: [0.5; 3]
+            })
+        );
+        assert_eq!(solid("torus"), Shape::Torus.solid().unwrap());
+        assert_eq!(solid("torus()"), Shape::Torus.solid().unwrap());
+        assert_eq!(
+            solid("move(-1, +2, 1e-1, sphere(1))"),
+            Solid::Sphere { radius: 1.0 }.translated([-1.0, 2.0, 0.1])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text: &str| text.parse::<Solid>().unwrap_err();
+        assert_eq!(error(""), "expected a solid");
+        assert_eq!(error("(sphere)"), "expected a solid, found `(`");
+        assert_eq!(
+            error("sphere(1) sphere(2)"),
+            "unexpected `s` after the end of the solid"
+        );
+        assert_eq!(
+            error("sphere(1, 2)"),
+            "wrong arguments to `sphere`, expected sphere(radius)"
+        );
+        assert_eq!(
+            error("subtract(sphere(1))"),
+            "wrong arguments to `subtract`, expected subtract(solid, cutouts...)"
+        );
+        assert_eq!(
+            error("blob(1)"),
+            "unknown solid `blob`, expected torus, sphere, box, cube, cylinder, cone, move, \
+             rotate, union, intersect, subtract or blend"
+        );
+        assert_eq!(
+            error("mobius"),
+            "`mobius` isn't solid, so it can't be raymarched"
+        );
+        assert_eq!(
+            error("move(sphere(1), 1, 2, 3)"),
+            "the numbers for `move` go before any solids"
+        );
+        assert_eq!(
+            error("union(sphere(1) sphere(2))"),
+            "expected `,` or `)` in the arguments to `union`"
+        );
+        assert_eq!(
+            error("sphere(1"),
+            "expected `,` or `)` in the arguments to `sphere`"
+        );
+        assert_eq!(error("sphere(1x)"), "invalid number `1x`");
+        assert_eq!(error("move(-inf, 0, 0, torus)"), "invalid number `-inf`");
+        assert_eq!(error("sphere(+nan)"), "invalid number `+nan`");
+        assert_eq!(error("sphere(1e999)"), "invalid number `1e999`");
+    }
+
+    #[test]
+    fn sizes_must_be_positive() {
+        let error = |text: &str| text.parse::<Solid>().unwrap_err();
+        assert_eq!(
+            error("sphere(-1)"),
+            "the radius of `sphere` must be greater than zero, not -1"
+        );
+        assert_eq!(
+            error("torus(1, 0)"),
+            "the major_radius of `torus` must be greater than zero, not 0"
+        );
+        assert_eq!(
+            error("union(torus, box(1, -0, 1))"),
+            "the half_y of `box` must be greater than zero, not -0"
+        );
+        assert_eq!(
+            error("cube(-0.5)"),
+            "the half_size of `cube` must be greater than zero, not -0.5"
+        );
+        assert_eq!(
+            error("cylinder(1, -2)"),
+            "the half_height of `cylinder` must be greater than zero, not -2"
+        );
+        assert_eq!(
+            error("cone(0, 3)"),
+            "the radius of `cone` must be greater than zero, not 0"
+        );
+
+        // NaN can't be written out, but it's turned down all the same
+        assert_eq!(
+            build("sphere", &[f64::NAN], Vec::new()).unwrap_err(),
+            "the radius of `sphere` must be greater than zero, not NaN"
+        );
+    }
+}
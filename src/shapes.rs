@@ -0,0 +1,563 @@
+// shapes.rs
+//
+// The built-in shapes, each one a parametric surface sized to spin in the same space as
+// the donut.
+
+use std::{
+    f64::consts::{PI, TAU},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{
+    sdf::Solid,
+    surface::{numeric_normal, Sample, Surface},
+};
+
+// How far the fixed-point renderer's angles get in one trip around the donut: 90 steps of
+// 9/128 radians, or 324 steps of 5/256 radians. It's a little more than a full turn, but
+// sampling the same angles keeps the two renderers comparable.
+const FIXED_POINT_TURN: f64 = 6.328125;
+
+const TORUS: Torus = Torus {
+    minor_radius: 1.0,
+    major_radius: 2.0,
+};
+const SPHERE: Sphere = Sphere { radius: 2.5 };
+const CUBE: Cube = Cube { half_size: 1.7 };
+const CYLINDER: Cylinder = Cylinder {
+    radius: 1.5,
+    half_height: 2.0,
+};
+const CONE: Cone = Cone {
+    radius: 2.0,
+    height: 3.5,
+};
+const MOBIUS: Mobius = Mobius {
+    radius: 2.0,
+    half_width: 1.0,
+};
+const KLEIN: Klein = Klein { scale: 1.35 };
+
+/// One of the built-in shapes, at its default size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// The donut.
+    #[default]
+    Torus,
+    Sphere,
+    Cube,
+    Cylinder,
+    Cone,
+    Mobius,
+    Klein,
+}
+
+impl Shape {
+    fn surface(self) -> &'static dyn Surface {
+        match self {
+            Shape::Torus => &TORUS,
+            Shape::Sphere => &SPHERE,
+            Shape::Cube => &CUBE,
+            Shape::Cylinder => &CYLINDER,
+            Shape::Cone => &CONE,
+            Shape::Mobius => &MOBIUS,
+            Shape::Klein => &KLEIN,
+        }
+    }
+
+    /// The shape as a [`Solid`] to raymarch, at the same size, if it has an inside at all.
+    /// The Möbius strip and Klein bottle don't.
+    pub fn solid(self) -> Option<Solid> {
+        match self {
+            Shape::Torus => Some(Solid::Torus {
+                minor_radius: TORUS.minor_radius,
+                major_radius: TORUS.major_radius,
+            }),
+            Shape::Sphere => Some(Solid::Sphere {
+                radius: SPHERE.radius,
+            }),
+            Shape::Cube => Some(Solid::Box {
+                half_size: [CUBE.half_size; 3],
+            }),
+            Shape::Cylinder => Some(Solid::Cylinder {
+                radius: CYLINDER.radius,
+                half_height: CYLINDER.half_height,
+            }),
+            Shape::Cone => Some(Solid::Cone {
+                radius: CONE.radius,
+                height: CONE.height,
+            }),
+            Shape::Mobius | Shape::Klein => None,
+        }
+    }
+}
+
+impl Surface for Shape {
+    fn domain(&self) -> [Range<f64>; 2] {
+        self.surface().domain()
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        self.surface().steps()
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        self.surface().sample(u, v)
+    }
+
+    fn two_sided(&self) -> bool {
+        self.surface().two_sided()
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "torus" | "donut" => Ok(Shape::Torus),
+            "sphere" => Ok(Shape::Sphere),
+            "cube" => Ok(Shape::Cube),
+            "cylinder" => Ok(Shape::Cylinder),
+            "cone" => Ok(Shape::Cone),
+            "mobius" | "möbius" => Ok(Shape::Mobius),
+            "klein" => Ok(Shape::Klein),
+            _ => Err(format!(
+                "unknown shape `{s}`, expected torus, sphere, cube, cylinder, cone, mobius \
+                 or klein"
+            )),
+        }
+    }
+}
+
+/// The donut: a circle swept around another, bigger circle.
+///
+/// u goes around the tube, and v around the hole in the middle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Torus {
+    /// The radius of the tube, R1 in the original code.
+    pub minor_radius: f64,
+    /// The distance from the center of the hole to the middle of the tube, R2 in the
+    /// original code.
+    pub major_radius: f64,
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        TORUS
+    }
+}
+
+impl Surface for Torus {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..FIXED_POINT_TURN, 0.0..FIXED_POINT_TURN]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [90, 324]
+    }
+
+    fn sample(&self, theta: f64, phi: f64) -> Sample {
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        let circle = self.major_radius + self.minor_radius * cos_theta;
+        Sample {
+            point: [
+                circle * cos_phi,
+                circle * sin_phi,
+                self.minor_radius * sin_theta,
+            ],
+            normal: [cos_theta * cos_phi, cos_theta * sin_phi, sin_theta],
+        }
+    }
+}
+
+/// A sphere around the origin.
+///
+/// u goes from pole to pole, and v around the equator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+    pub radius: f64,
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        SPHERE
+    }
+}
+
+impl Surface for Sphere {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..PI, 0.0..TAU]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [128, 256]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        let (sin_u, cos_u) = u.sin_cos();
+        let (sin_v, cos_v) = v.sin_cos();
+
+        let normal = [sin_u * cos_v, sin_u * sin_v, cos_u];
+        Sample {
+            point: normal.map(|n| n * self.radius),
+            normal,
+        }
+    }
+}
+
+/// A cube around the origin.
+///
+/// Each whole number of u is one face, and the fraction of u and v pick a point across it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cube {
+    /// Half the length of an edge.
+    pub half_size: f64,
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        CUBE
+    }
+}
+
+impl Surface for Cube {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..6.0, 0.0..1.0]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [6 * 56, 56]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        let face = (u as usize).min(5);
+        let across = |t: f64| (2.0 * t - 1.0) * self.half_size;
+
+        // Faces come in pairs along each axis, the positive one first
+        let axis = face / 2;
+        let sign = if face.is_multiple_of(2) { 1.0 } else { -1.0 };
+
+        let mut point = [0.0; 3];
+        point[axis] = sign * self.half_size;
+        point[(axis + 1) % 3] = across(u - face as f64);
+        point[(axis + 2) % 3] = across(v);
+
+        let mut normal = [0.0; 3];
+        normal[axis] = sign;
+
+        Sample { point, normal }
+    }
+}
+
+/// A cylinder around the z-axis, closed at both ends.
+///
+/// u runs across the bottom, up the side and back across the top, by distance along the
+/// surface, and v goes around the axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cylinder {
+    pub radius: f64,
+    pub half_height: f64,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        CYLINDER
+    }
+}
+
+impl Surface for Cylinder {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..2.0 * (self.radius + self.half_height), 0.0..TAU]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [120, 160]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        let (sin_v, cos_v) = v.sin_cos();
+        let side = self.radius + 2.0 * self.half_height;
+
+        let (radius, z, normal) = if u < self.radius {
+            (u, -self.half_height, [0.0, 0.0, -1.0])
+        } else if u < side {
+            (
+                self.radius,
+                u - self.radius - self.half_height,
+                [cos_v, sin_v, 0.0],
+            )
+        } else {
+            (self.radius + side - u, self.half_height, [0.0, 0.0, 1.0])
+        };
+
+        Sample {
+            point: [radius * cos_v, radius * sin_v, z],
+            normal,
+        }
+    }
+}
+
+/// A cone around the z-axis, with its base below the origin and its tip above.
+///
+/// u runs across the base and then up the side to the tip, by distance along the surface,
+/// and v goes around the axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cone {
+    /// The radius of the base.
+    pub radius: f64,
+    pub height: f64,
+}
+
+impl Cone {
+    // The length of the side, from the edge of the base to the tip
+    fn slant(&self) -> f64 {
+        self.radius.hypot(self.height)
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        CONE
+    }
+}
+
+impl Surface for Cone {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..self.radius + self.slant(), 0.0..TAU]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [110, 160]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        let (sin_v, cos_v) = v.sin_cos();
+        let slant = self.slant();
+
+        // Put the base a little further from the origin than the tip, so the cone spins
+        // roughly about its middle
+        let base = -0.43 * self.height;
+
+        let (radius, z, normal) = if u < self.radius {
+            (u, base, [0.0, 0.0, -1.0])
+        } else {
+            let t = (u - self.radius) / slant;
+            (
+                self.radius * (1.0 - t),
+                base + self.height * t,
+                [
+                    self.height * cos_v / slant,
+                    self.height * sin_v / slant,
+                    self.radius / slant,
+                ],
+            )
+        };
+
+        Sample {
+            point: [radius * cos_v, radius * sin_v, z],
+            normal,
+        }
+    }
+}
+
+/// A Möbius strip: a band with a half twist, so it only has the one side.
+///
+/// u goes across the band, and v around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mobius {
+    /// The distance from the center to the middle of the band.
+    pub radius: f64,
+    pub half_width: f64,
+}
+
+impl Mobius {
+    fn point(&self, u: f64, v: f64) -> [f64; 3] {
+        let (sin_half, cos_half) = (v / 2.0).sin_cos();
+        let (sin_v, cos_v) = v.sin_cos();
+        let circle = self.radius + u * cos_half;
+        [circle * cos_v, circle * sin_v, u * sin_half]
+    }
+}
+
+impl Default for Mobius {
+    fn default() -> Self {
+        MOBIUS
+    }
+}
+
+impl Surface for Mobius {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [-self.half_width..self.half_width, 0.0..TAU]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [40, 324]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        Sample {
+            point: self.point(u, v),
+            normal: numeric_normal(|u, v| self.point(u, v), u, v),
+        }
+    }
+
+    fn two_sided(&self) -> bool {
+        true
+    }
+}
+
+/// A Klein bottle, in the familiar bottle shape that passes through itself.
+///
+/// u goes along the bottle, and v around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Klein {
+    /// How much bigger to make the bottle than the usual parametrization, which is about
+    /// 3.3 by 4.2 by 1.5.
+    pub scale: f64,
+}
+
+impl Klein {
+    fn point(&self, u: f64, v: f64) -> [f64; 3] {
+        let (sin_u, cos_u) = u.sin_cos();
+        let (sin_v, cos_v) = v.sin_cos();
+        let cos_u_to = |power: i32| cos_u.powi(power);
+
+        let x = -2.0 / 15.0
+            * cos_u
+            * (3.0 * cos_v - 30.0 * sin_u + 90.0 * cos_u_to(4) * sin_u
+                - 60.0 * cos_u_to(6) * sin_u
+                + 5.0 * cos_u * cos_v * sin_u);
+        let y = -1.0 / 15.0
+            * sin_u
+            * (3.0 * cos_v - 3.0 * cos_u_to(2) * cos_v - 48.0 * cos_u_to(4) * cos_v
+                + 48.0 * cos_u_to(6) * cos_v
+                - 60.0 * sin_u
+                + 5.0 * cos_u * cos_v * sin_u
+                - 5.0 * cos_u_to(3) * cos_v * sin_u
+                - 80.0 * cos_u_to(5) * cos_v * sin_u
+                + 80.0 * cos_u_to(7) * cos_v * sin_u);
+        let z = 2.0 / 15.0 * (3.0 + 5.0 * cos_u * sin_u) * sin_v;
+
+        // Move the middle of the bottle to the origin, so it spins in place
+        [
+            (x - 0.15) * self.scale,
+            (y - 2.1) * self.scale,
+            z * self.scale,
+        ]
+    }
+}
+
+impl Default for Klein {
+    fn default() -> Self {
+        KLEIN
+    }
+}
+
+impl Surface for Klein {
+    fn domain(&self) -> [Range<f64>; 2] {
+        [0.0..PI, 0.0..TAU]
+    }
+
+    fn steps(&self) -> [u32; 2] {
+        [200, 160]
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Sample {
+        Sample {
+            point: self.point(u, v),
+            normal: numeric_normal(|u, v| self.point(u, v), u, v),
+        }
+    }
+
+    fn two_sided(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{length, normalize};
+
+    const SHAPES: [Shape; 7] = [
+        Shape::Torus,
+        Shape::Sphere,
+        Shape::Cube,
+        Shape::Cylinder,
+        Shape::Cone,
+        Shape::Mobius,
+        Shape::Klein,
+    ];
+
+    fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+        a.iter().zip(b).map(|(a, b)| a * b).sum()
+    }
+
+    // Points spread across the domain, picked to stay clear of the edges between the
+    // cube's faces and the cylinder's and cone's ends and sides
+    fn parameters(surface: &impl Surface) -> Vec<(f64, f64)> {
+        let [u, v] = surface.domain();
+        let along = |range: &Range<f64>, t: f64| range.start + t * (range.end - range.start);
+        let mut parameters = Vec::new();
+        for s in [0.13, 0.37, 0.61, 0.87] {
+            for t in [0.1, 0.45, 0.8] {
+                parameters.push((along(&u, s), along(&v, t)));
+            }
+        }
+        parameters
+    }
+
+    #[test]
+    fn normals_are_unit_length_and_across_the_surface() {
+        const STEP: f64 = 1e-6;
+
+        for shape in SHAPES {
+            for (u, v) in parameters(&shape) {
+                let Sample { point, normal } = shape.sample(u, v);
+                assert!(
+                    (length(normal) - 1.0).abs() < 1e-9,
+                    "{shape:?} at ({u}, {v}): {normal:?}"
+                );
+
+                let tangent = |a: [f64; 3], b: [f64; 3]| normalize([0, 1, 2].map(|i| a[i] - b[i]));
+                let along_u = tangent(shape.sample(u + STEP, v).point, point);
+                let along_v = tangent(shape.sample(u, v + STEP).point, point);
+                for along in [along_u, along_v] {
+                    assert!(
+                        dot(normal, along).abs() < 1e-4,
+                        "{shape:?} at ({u}, {v}): {normal:?} against {along:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normals_point_outward() {
+        // The sphere's and the donut's point straight out from the middle and the middle of
+        // the tube
+        for (u, v) in parameters(&SPHERE) {
+            let Sample { point, normal } = SPHERE.sample(u, v);
+            assert!(dot(normal, normalize(point)) > 1.0 - 1e-9);
+        }
+        for (u, v) in parameters(&TORUS) {
+            let Sample { point, normal } = TORUS.sample(u, v);
+            let middle = [v.cos(), v.sin(), 0.0].map(|n| n * TORUS.major_radius);
+            let out = normalize([0, 1, 2].map(|i| point[i] - middle[i]));
+            assert!(dot(normal, out) > 1.0 - 1e-9, "({u}, {v})");
+        }
+
+        // The rest that have an inside are convex around the origin, so their normals all
+        // lean away from it
+        for shape in [Shape::Cube, Shape::Cylinder, Shape::Cone] {
+            for (u, v) in parameters(&shape) {
+                let Sample { point, normal } = shape.sample(u, v);
+                assert!(dot(normal, point) > 0.0, "{shape:?} at ({u}, {v})");
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+// style.rs
+//
+// Turns a rendered frame into the text that actually gets printed.
+
+use std::str::FromStr;
+
+use crate::{
+    color::{ColorMode, Gradient, Rgb},
+    frame::{Frame, Pixel},
+    ramp::Ramp,
+};
+
+/// How the pixels of a frame are laid out across the characters of the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// One pixel per character, drawn with the luminance ramp, like the original.
+    #[default]
+    Glyphs,
+    /// A 2x4 grid of pixels per character, drawn as Unicode braille dots.
+    Braille,
+    /// Two pixels per character, one above the other, drawn as half blocks with separate
+    /// foreground and background colors.
+    HalfBlock,
+}
+
+// What gets printed for a single character of output
+struct Cell {
+    character: char,
+    foreground: Option<Pixel>,
+    background: Option<Pixel>,
+}
+
+impl Mode {
+    /// How many pixels wide and tall each character covers.
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            Mode::Glyphs => (1, 1),
+            Mode::Braille => (2, 4),
+            Mode::HalfBlock => (1, 2),
+        }
+    }
+
+    /// How many times taller than it is wide each pixel is, assuming characters are
+    /// about twice as tall as they are wide.
+    pub fn aspect(self) -> f64 {
+        let (width, height) = self.cell_size();
+        2.0 * width as f64 / height as f64
+    }
+
+    /// The size of frame, in pixels, that fills `columns` by `rows` characters.
+    pub fn frame_size(self, columns: usize, rows: usize) -> (usize, usize) {
+        let (width, height) = self.cell_size();
+        (columns * width, rows * height)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "glyphs" => Ok(Mode::Glyphs),
+            "braille" => Ok(Mode::Braille),
+            "half-block" => Ok(Mode::HalfBlock),
+            _ => Err(format!(
+                "unknown mode `{s}`, expected glyphs, braille or half-block"
+            )),
+        }
+    }
+}
+
+// The bits for each dot of a braille character, indexed by column then row
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+// A 4x4 ordered dithering matrix, used to decide which braille dots to light when there's
+// no color to show the luminance with instead
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// How a [`Frame`] should be printed.
+#[derive(Clone, Debug, Default)]
+pub struct Style {
+    /// How pixels map onto characters.
+    pub mode: Mode,
+    /// The characters that luminance gets mapped onto in [`Mode::Glyphs`].
+    pub ramp: Ramp,
+    /// Which escape codes to color the output with, if any.
+    pub color: ColorMode,
+    /// The colors that luminance gets mapped onto, from darkest to brightest.
+    pub gradient: Gradient,
+    /// Whether to darken the parts of the donut that are farther away.
+    pub depth_shading: bool,
+}
+
+impl Style {
+    /// Prints `frame` as text, one line per row.
+    pub fn paint(&self, frame: &Frame) -> String {
+        self.paint_rows(frame, false)
+    }
+
+    /// Prints `frame` as text like [`paint`](Self::paint), but leaves off the blank cells at
+    /// the end of each row, for text that isn't going to be drawn over another frame.
+    pub fn paint_trimmed(&self, frame: &Frame) -> String {
+        self.paint_rows(frame, true)
+    }
+
+    fn paint_rows(&self, frame: &Frame, trim: bool) -> String {
+        let depth_range = frame.depth_range();
+        let (cell_width, cell_height) = self.mode.cell_size();
+        let columns = frame.width().div_ceil(cell_width);
+        let rows = frame.height().div_ceil(cell_height);
+        let mut output = String::new();
+
+        for row in 0..rows {
+            // Only emit escape codes when the colors actually change from one cell to the
+            // next, and keep track of whether a background color needs clearing
+            let mut current_codes = String::new();
+            let mut has_background = false;
+
+            // The blank cells at the end have to be left off before any colors go in, or
+            // they'd end up inside the last run of color
+            let end = if trim {
+                (0..columns)
+                    .rev()
+                    .find(|&column| self.cell(frame, column, row).is_some())
+                    .map_or(0, |column| column + 1)
+            } else {
+                columns
+            };
+
+            for column in 0..end {
+                let Some(cell) = self.cell(frame, column, row) else {
+                    if has_background {
+                        output.push_str("\x1b[0m");
+                        current_codes.clear();
+                        has_background = false;
+                    }
+                    output.push(' ');
+                    continue;
+                };
+
+                let foreground = cell
+                    .foreground
+                    .and_then(|pixel| self.color.foreground(self.color_of(&pixel, depth_range)));
+                let background = cell
+                    .background
+                    .and_then(|pixel| self.color.background(self.color_of(&pixel, depth_range)));
+                let has_new_background = background.is_some();
+                let codes = [foreground, background]
+                    .into_iter()
+                    .flatten()
+                    .collect::<String>();
+                if codes != current_codes {
+                    if !current_codes.is_empty() {
+                        output.push_str("\x1b[0m");
+                    }
+                    output.push_str(&codes);
+                    has_background = has_new_background;
+                    current_codes = codes;
+                }
+                output.push(cell.character);
+            }
+
+            if !current_codes.is_empty() {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    // Works out what to print for the cell at `column` and `row`, or `None` if the cell
+    // should be left blank
+    fn cell(&self, frame: &Frame, column: usize, row: usize) -> Option<Cell> {
+        match self.mode {
+            Mode::Glyphs => frame.pixel(column, row).map(|pixel| Cell {
+                character: self.ramp.glyph(pixel.luminance),
+                foreground: Some(pixel),
+                background: None,
+            }),
+            Mode::Braille => self.braille(frame, column, row),
+            Mode::HalfBlock => {
+                let top = frame.pixel(column, row * 2);
+                let bottom = frame.pixel(column, row * 2 + 1);
+                match (top, bottom) {
+                    (None, None) => None,
+                    // Without color, all that can be shown is which halves are covered
+                    (Some(_), Some(_)) if self.color == ColorMode::None => Some(Cell {
+                        character: '█',
+                        foreground: None,
+                        background: None,
+                    }),
+                    (Some(_), _) => Some(Cell {
+                        character: '▀',
+                        foreground: top,
+                        background: bottom,
+                    }),
+                    (None, Some(_)) => Some(Cell {
+                        character: '▄',
+                        foreground: bottom,
+                        background: None,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn braille(&self, frame: &Frame, column: usize, row: usize) -> Option<Cell> {
+        let mut bits = 0;
+        let mut lit = 0;
+        let mut luminance = 0.0;
+        let mut depth = f32::INFINITY;
+        let mut color = None;
+
+        for (dx, column_bits) in BRAILLE_DOTS.iter().enumerate() {
+            for (dy, bit) in column_bits.iter().enumerate() {
+                let (x, y) = (column * 2 + dx, row * 4 + dy);
+                let Some(pixel) = frame.pixel(x, y) else {
+                    continue;
+                };
+
+                // With color available, every dot gets lit and the color shows the luminance.
+                // Without it, dither the dots so brighter areas get more of them, keeping a
+                // few lit even in the darkest areas so the outline doesn't vanish.
+                let threshold = (f32::from(BAYER[y % 4][x % 4]) + 0.5) / 16.0;
+                if self.color == ColorMode::None && 0.1 + 0.9 * pixel.luminance < threshold {
+                    continue;
+                }
+
+                bits |= bit;
+                lit += 1;
+                luminance += pixel.luminance;
+                if pixel.depth < depth {
+                    depth = pixel.depth;
+                    color = pixel.color;
+                }
+            }
+        }
+
+        let character = char::from_u32(0x2800 + bits)?;
+        (lit > 0).then(|| Cell {
+            character,
+            foreground: Some(Pixel {
+                luminance: luminance / lit as f32,
+                depth,
+                color,
+            }),
+            background: None,
+        })
+    }
+
+    // Picks the color for a pixel, dimming it by its depth within the frame if asked to.
+    // Pixels that come with a color of their own keep it.
+    fn color_of(&self, pixel: &Pixel, depth_range: Option<(f32, f32)>) -> Rgb {
+        let mut color = pixel
+            .color
+            .unwrap_or_else(|| self.gradient.sample(pixel.luminance));
+
+        if let (true, Some((near, far))) = (self.depth_shading, depth_range) {
+            let distance = if far > near {
+                (pixel.depth - near) / (far - near)
+            } else {
+                0.0
+            };
+            // Keep the back of the donut from disappearing into the background entirely
+            color = color.dim(1.0 - 0.6 * distance);
+        }
+
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = Rgb(255, 0, 0);
+    const BLUE: Rgb = Rgb(0, 0, 255);
+
+    fn pixel(luminance: f32, color: Option<Rgb>) -> Pixel {
+        Pixel {
+            luminance,
+            depth: 1.0,
+            color,
+        }
+    }
+
+    #[test]
+    fn colors_change_only_between_runs() {
+        let mut frame = Frame::new(4, 1);
+        frame.plot_pixel(0, 0, pixel(1.0, Some(RED)));
+        frame.plot_pixel(1, 0, pixel(0.0, Some(RED)));
+        frame.plot_pixel(2, 0, pixel(1.0, Some(BLUE)));
+
+        let style = Style {
+            color: ColorMode::TrueColor,
+            ..Style::default()
+        };
+        assert_eq!(
+            style.paint(&frame),
+            "\x1b[38;2;255;0;0m@.\x1b[0m\x1b[38;2;0;0;255m@ \x1b[0m\n"
+        );
+        assert_eq!(
+            style.paint_trimmed(&frame),
+            "\x1b[38;2;255;0;0m@.\x1b[0m\x1b[38;2;0;0;255m@\x1b[0m\n"
+        );
+
+        // Without color, it's only the glyphs
+        assert_eq!(Style::default().paint(&frame), "@.@ \n");
+    }
+
+    #[test]
+    fn braille_dots() {
+        let mut frame = Frame::new(6, 8);
+        // Dots down the left, one in the middle of the right and the two at the bottom
+        for (x, y) in [(0, 0), (1, 1), (0, 3), (1, 3)] {
+            frame.plot(x, y, 1.0, 1.0);
+        }
+        // Dark pixels only light a few of their dots when there's no color to show them
+        // with, and bright ones light them all
+        for x in 2..6 {
+            for y in 0..4 {
+                frame.plot(x, y, if x < 4 { 0.0 } else { 1.0 }, 1.0);
+            }
+        }
+        let style = Style {
+            mode: Mode::Braille,
+            ..Style::default()
+        };
+        assert_eq!(style.paint(&frame), "\u{28d1}\u{2804}\u{28ff}\n   \n");
+    }
+
+    #[test]
+    fn braille_takes_the_nearest_color() {
+        let mut frame = Frame::new(2, 4);
+        frame.plot_pixel(
+            0,
+            0,
+            Pixel {
+                luminance: 0.0,
+                depth: 2.0,
+                color: Some(RED),
+            },
+        );
+        frame.plot_pixel(
+            1,
+            0,
+            Pixel {
+                luminance: 0.0,
+                depth: 1.0,
+                color: Some(BLUE),
+            },
+        );
+        let style = Style {
+            mode: Mode::Braille,
+            color: ColorMode::TrueColor,
+            ..Style::default()
+        };
+        // With color to show how dark they are, all the dots get lit
+        assert_eq!(style.paint(&frame), "\x1b[38;2;0;0;255m\u{2809}\x1b[0m\n");
+    }
+
+    #[test]
+    fn half_blocks() {
+        let mut frame = Frame::new(4, 2);
+        frame.plot_pixel(0, 0, pixel(1.0, Some(RED)));
+        frame.plot_pixel(0, 1, pixel(1.0, Some(BLUE)));
+        frame.plot_pixel(1, 0, pixel(1.0, Some(RED)));
+        frame.plot_pixel(2, 1, pixel(1.0, Some(BLUE)));
+
+        // The top pixel colors the upper half block and the bottom one its background,
+        // or the lower half block when there's nothing above it
+        let style = Style {
+            mode: Mode::HalfBlock,
+            color: ColorMode::TrueColor,
+            ..Style::default()
+        };
+        assert_eq!(
+            style.paint(&frame),
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[0m\x1b[38;2;255;0;0m▀\x1b[0m\
+             \x1b[38;2;0;0;255m▄ \x1b[0m\n"
+        );
+
+        // Without color, all that shows is which halves are covered
+        let style = Style {
+            mode: Mode::HalfBlock,
+            ..Style::default()
+        };
+        assert_eq!(style.paint(&frame), "█▀▄ \n");
+    }
+
+    #[test]
+    fn half_block_backgrounds_stop_before_blank_cells() {
+        let mut frame = Frame::new(2, 2);
+        frame.plot_pixel(0, 0, pixel(1.0, Some(RED)));
+        frame.plot_pixel(0, 1, pixel(1.0, Some(BLUE)));
+        let style = Style {
+            mode: Mode::HalfBlock,
+            color: ColorMode::TrueColor,
+            ..Style::default()
+        };
+        assert_eq!(
+            style.paint(&frame),
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[0m \n"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// surface.rs
+//
+// The shapes the floating-point renderer can draw, described as surfaces sampled over a
+// (u, v) parameter domain.
+
+use std::{fmt::Debug, ops::Range};
+
+/// A point on a surface, and the direction the surface faces there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Where the point is, before any rotation, with the origin at the center of rotation.
+    pub point: [f64; 3],
+    /// The unit normal of the surface at the point.
+    pub normal: [f64; 3],
+}
+
+/// A shape that can be drawn by sampling it over a grid of (u, v) parameters.
+///
+/// Shapes should fit within a sphere of radius 3 around the origin, the same as the
+/// donut, to fill the frame the way it does.
+pub trait Surface: Debug {
+    /// The ranges of u and v to sample over.
+    fn domain(&self) -> [Range<f64>; 2];
+
+    /// How many steps to take across the u and v ranges at the classic 80x22 size. The
+    /// renderer takes more of them for bigger frames.
+    fn steps(&self) -> [u32; 2];
+
+    /// The point and normal at (u, v).
+    fn sample(&self, u: f64, v: f64) -> Sample;
+
+    /// Whether both sides of the surface can be seen, so it should be lit on whichever side
+    /// faces the viewer rather than only on the side its normal points out of. Closed
+    /// shapes can leave this as `false`.
+    fn two_sided(&self) -> bool {
+        false
+    }
+}
+
+/// Works out the unit normal at (u, v) of a surface given by `point`, from how the point
+/// moves as u and v change. Handy for surfaces whose normals are a pain to find by hand.
+///
+/// Returns a zero vector where the surface is degenerate and has no normal.
+pub fn numeric_normal(point: impl Fn(f64, f64) -> [f64; 3], u: f64, v: f64) -> [f64; 3] {
+    const STEP: f64 = 1e-5;
+
+    let difference = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let along_u = difference(point(u + STEP, v), point(u - STEP, v));
+    let along_v = difference(point(u, v + STEP), point(u, v - STEP));
+
+    let normal = [
+        along_u[1] * along_v[2] - along_u[2] * along_v[1],
+        along_u[2] * along_v[0] - along_u[0] * along_v[2],
+        along_u[0] * along_v[1] - along_u[1] * along_v[0],
+    ];
+    let length = normal.iter().map(|n| n * n).sum::<f64>().sqrt();
+    if length > f64::EPSILON {
+        normal.map(|n| n / length)
+    } else {
+        [0.0; 3]
+    }
+}
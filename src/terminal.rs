@@ -0,0 +1,229 @@
+// terminal.rs
+//
+// Helpers for working out what the terminal we're drawing to looks like,
+// and for leaving it the way we found it.
+
+use std::{
+    env,
+    io::{self, Write},
+    panic,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Returns the size of the terminal attached to stdout as `(columns, rows)`.
+///
+/// The terminal is asked directly where the platform allows it, falling back on the
+/// `COLUMNS` and `LINES` environment variables. Returns `None` if neither works out.
+pub fn size() -> Option<(usize, usize)> {
+    let (columns, rows) = sys::window_size()
+        .map(|(columns, rows)| (usize::from(columns), usize::from(rows)))
+        .or_else(|| {
+            let columns = env::var("COLUMNS").ok()?.parse().ok()?;
+            let rows = env::var("LINES").ok()?.parse().ok()?;
+            Some((columns, rows))
+        })?;
+    (columns > 0 && rows > 0).then_some((columns, rows))
+}
+
+// Set from the SIGWINCH handler, and cleared again by `take_resized()`
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+/// Starts listening for the terminal being resized, after which [`take_resized`] reports
+/// whether it has happened. Returns `false` if resizes can't be watched on this platform.
+pub fn watch_resize() -> bool {
+    sys::watch_resize()
+}
+
+/// Returns `true` if the terminal has been resized since the last time this was called.
+pub fn take_resized() -> bool {
+    RESIZED.swap(false, Ordering::Relaxed)
+}
+
+// Set from the SIGINT and SIGTERM handlers
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Starts listening for Ctrl-C (SIGINT) and SIGTERM, after which [`interrupted`] reports
+/// whether either has arrived instead of the process being killed outright. Returns `false`
+/// if they can't be watched on this platform.
+pub fn watch_interrupt() -> bool {
+    sys::watch_interrupt()
+}
+
+/// Returns `true` once SIGINT or SIGTERM has been received.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+// Whether a `Guard` currently has the terminal set up, and whether that includes the
+// alternate screen. These are statics so the panic hook can see them too.
+static GUARD_ACTIVE: AtomicBool = AtomicBool::new(false);
+static GUARD_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Sets the terminal up for animation, and puts it back the way it was when dropped.
+///
+/// While the guard is alive the cursor is hidden and, optionally, drawing happens on the
+/// alternate screen buffer so the animation doesn't end up in the scrollback. The terminal
+/// gets restored on drop, which covers both a normal exit and unwinding from a panic, and a
+/// panic hook makes sure that happens before the panic message is printed.
+pub struct Guard {
+    _private: (),
+}
+
+impl Guard {
+    /// Hides the cursor and, if `alternate_screen` is set, switches to the alternate screen.
+    pub fn new(alternate_screen: bool) -> Guard {
+        GUARD_ALTERNATE_SCREEN.store(alternate_screen, Ordering::Relaxed);
+        GUARD_ACTIVE.store(true, Ordering::Relaxed);
+
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            previous_hook(info);
+        }));
+
+        let mut stdout = io::stdout();
+        if alternate_screen {
+            // Switch screens, then move the cursor to the top-left of the fresh screen
+            let _ = write!(stdout, "\x1b[?1049h\x1b[H");
+        }
+        let _ = write!(stdout, "\x1b[?25l");
+        let _ = stdout.flush();
+
+        Guard { _private: () }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// Resets colors, shows the cursor and leaves the alternate screen, if the guard is still
+// active. The cursor is otherwise already sitting just below the last frame.
+fn restore() {
+    if !GUARD_ACTIVE.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "\x1b[0m\x1b[?25h");
+    if GUARD_ALTERNATE_SCREEN.load(Ordering::Relaxed) {
+        let _ = write!(stdout, "\x1b[?1049l");
+    }
+    let _ = stdout.flush();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod sys {
+    use std::ffi::{c_int, c_ulong};
+
+    // The `winsize` struct filled in by the TIOCGWINSZ ioctl
+    #[repr(C)]
+    #[derive(Default)]
+    struct WindowSize {
+        rows: u16,
+        columns: u16,
+        _x_pixels: u16,
+        _y_pixels: u16,
+    }
+
+    // Most Linux architectures share the same ioctl numbers, but a few kept the BSD-style
+    // ones that macOS uses
+    #[cfg(all(
+        any(target_os = "linux", target_os = "android"),
+        not(any(
+            target_arch = "mips",
+            target_arch = "mips64",
+            target_arch = "mips32r6",
+            target_arch = "mips64r6",
+            target_arch = "powerpc",
+            target_arch = "powerpc64",
+            target_arch = "sparc",
+            target_arch = "sparc64",
+        ))
+    ))]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(any(
+        target_os = "macos",
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "mips32r6",
+        target_arch = "mips64r6",
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "sparc",
+        target_arch = "sparc64",
+    ))]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+
+    const STDOUT_FILENO: c_int = 1;
+
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+    // MIPS numbers its signals the way System V did, which moves this one
+    #[cfg(not(any(
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "mips32r6",
+        target_arch = "mips64r6",
+    )))]
+    const SIGWINCH: c_int = 28;
+    #[cfg(any(
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "mips32r6",
+        target_arch = "mips64r6",
+    ))]
+    const SIGWINCH: c_int = 20;
+    const SIG_ERR: usize = usize::MAX;
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    // Only async-signal-safe work is allowed in here, so just raise the flag
+    extern "C" fn on_resize(_signum: c_int) {
+        super::RESIZED.store(true, super::Ordering::Relaxed);
+    }
+
+    extern "C" fn on_interrupt(_signum: c_int) {
+        super::INTERRUPTED.store(true, super::Ordering::Relaxed);
+    }
+
+    pub fn watch_resize() -> bool {
+        // SAFETY: the handler only touches an atomic, which is safe to do from a signal handler
+        unsafe { signal(SIGWINCH, on_resize) != SIG_ERR }
+    }
+
+    pub fn watch_interrupt() -> bool {
+        // SAFETY: as above, the handler only touches an atomic
+        unsafe {
+            signal(SIGINT, on_interrupt) != SIG_ERR && signal(SIGTERM, on_interrupt) != SIG_ERR
+        }
+    }
+
+    pub fn window_size() -> Option<(u16, u16)> {
+        let mut size = WindowSize::default();
+        // SAFETY: TIOCGWINSZ writes a single `winsize` struct through the pointer it's given,
+        // which `WindowSize` matches the layout of.
+        let result = unsafe { ioctl(STDOUT_FILENO, TIOCGWINSZ, &mut size as *mut WindowSize) };
+        (result == 0).then_some((size.columns, size.rows))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+mod sys {
+    pub fn window_size() -> Option<(u16, u16)> {
+        None
+    }
+
+    pub fn watch_resize() -> bool {
+        false
+    }
+
+    pub fn watch_interrupt() -> bool {
+        false
+    }
+}